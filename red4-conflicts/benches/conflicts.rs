@@ -0,0 +1,66 @@
+//! Conflict computation on synthetic archives with 100k entries each.
+//!
+//! Run with `cargo bench`
+
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use red4_conflicts::{cache::ArchiveIndex, engine::compute_conflicts, ArchiveViewModel};
+
+const ENTRIES_PER_ARCHIVE: u64 = 100_000;
+const ITERATIONS: u32 = 5;
+
+/// Create archives whose file hashes overlap with the next archive by `overlap` entries
+fn create_archives(count: u64, overlap: u64) -> Vec<(u64, ArchiveViewModel)> {
+    (0..count)
+        .map(|i| {
+            let start = i * (ENTRIES_PER_ARCHIVE - overlap);
+            let mut files = (start..start + ENTRIES_PER_ARCHIVE)
+                .map(|h| h.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+                .collect::<Vec<_>>();
+            files.sort();
+            let sha1 = files.iter().map(|h| [(h % 2) as u8; 20]).collect();
+
+            let vm = ArchiveViewModel {
+                file_name: format!("archive_{}.archive", i),
+                files: files.clone(),
+                index: Arc::new(ArchiveIndex {
+                    files,
+                    sha1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            (i, vm)
+        })
+        .collect()
+}
+
+fn bench(name: &str, count: u64, overlap: u64) {
+    let mut total = Duration::ZERO;
+    let mut conflicts = 0;
+    for _ in 0..ITERATIONS {
+        let archives = create_archives(count, overlap);
+        let start = Instant::now();
+        let report = black_box(compute_conflicts(archives));
+        total += start.elapsed();
+        conflicts = report.conflicts.len();
+    }
+
+    println!(
+        "{:<40} {:>10.2?} / iter ({} conflicts)",
+        name,
+        total / ITERATIONS,
+        conflicts
+    );
+}
+
+fn main() {
+    bench("2 archives, full overlap", 2, ENTRIES_PER_ARCHIVE);
+    bench("10 archives, half overlap", 10, ENTRIES_PER_ARCHIVE / 2);
+    bench("10 archives, no overlap", 10, 0);
+    bench("50 archives, 10% overlap", 50, ENTRIES_PER_ARCHIVE / 10);
+}
@@ -1,9 +1,24 @@
 use std::{collections::HashMap, env};
 
 use egui::{Color32, Popup, UiKind};
-use red4lib::{fnv1a64_hash_path, get_red4_hashes};
+use red4lib::fnv1a64_hash_path;
 
-use crate::{ArchiveViewModel, ETooltipVisuals, TemplateApp};
+use crate::backup::get_backups;
+use crate::cli::build_report;
+use crate::export::{export_report, EExportFormat};
+use crate::extract::extract_versions;
+use crate::graph::{to_dot, ConflictMatrix};
+use crate::inspector::{format_sha1, format_size, EEntrySortColumn};
+use crate::install::{find_installs, resolve_archive_folder, validate_archive_folder};
+use crate::patch::PATCH_ARCHIVE_NAME;
+use crate::pins::EPinStatus;
+use crate::profile::{compare_load_orders, ELoadOrderDiff};
+use crate::tree::{build_tree, FolderNode};
+use crate::watcher::ModWatcher;
+use crate::{
+    get_archive_path, get_backup_dir, is_redmod, ArchiveViewModel, ConflictReport, ETooltipVisuals,
+    TemplateApp,
+};
 
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
@@ -15,30 +30,68 @@ impl eframe::App for TemplateApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // first time, load hashes
         if self.hashes.is_empty() {
-            self.hashes = get_red4_hashes();
+            self.load_hashes();
         }
-        // first time, set game path to cwd
+        // first time, look for a game install or set game path to cwd
         if !self.game_path.exists() {
-            if let Ok(current_dir) = env::current_dir() {
+            if let Some(install) = find_installs().first() {
+                log::info!("Found game install at {}", install.display());
+                self.game_path = resolve_archive_folder(install);
+            } else if let Ok(current_dir) = env::current_dir() {
                 self.game_path = current_dir;
             }
         }
 
+        // swap in finished conflict checks
+        self.poll_scan();
+        if self.scan.is_some() {
+            ctx.request_repaint();
+        }
+
+        // reload when archives were added, removed or changed on disk
+        if self.watcher.as_mut().is_some_and(|w| w.poll()) {
+            log::info!("Mod folder changed, reloading load order");
+            self.reload_load_order();
+            self.generate_conflict_map();
+            self.last_load_order = Some(self.load_order.clone());
+        }
+        if self.watcher.as_ref().is_some_and(|w| w.is_pending()) {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
         // each frame we check the load order
        
         // auto-generate hashes on first load and load order change
         if let Some(last_load_order) = &self.last_load_order {
             if &self.load_order != last_load_order {
+                self.history.record(last_load_order.clone());
+
+                // REDmods can't be reordered, keep them last in deploy order
+                let redmods = last_load_order
+                    .iter()
+                    .filter(|n| is_redmod(n))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                self.load_order.retain(|n| !is_redmod(n));
+                self.load_order.extend(redmods);
+
                 self.generate_conflict_map();
                 self.last_load_order = Some(self.load_order.clone());
                 self.serialize_load_order();
+                self.save_profile();
             }
         } else {
             // first load
+            self.history.clear();
+            self.install_warnings = validate_archive_folder(&self.game_path);
+            if self.watcher.as_ref().map(|w| &w.game_path) != Some(&self.game_path) {
+                self.watcher = ModWatcher::new(&self.game_path, ctx);
+            }
             self.reload_load_order();
             self.generate_conflict_map();
             self.last_load_order = Some(self.load_order.clone());
             self.serialize_load_order();
+            self.save_profile();
         }
 
         // Menu bar
@@ -56,7 +109,14 @@ impl eframe::App for TemplateApp {
             self.conflicts_view(ui);
         });
 
-       
+        self.compare_profiles_view(ctx);
+        self.backups_view(ctx);
+        self.conflict_matrix_view(ctx);
+        self.file_tree_view(ctx);
+        self.inspector_view(ctx);
+        self.comparison_view(ctx);
+        self.patch_view(ctx);
+        self.pins_view(ctx);
     }
 }
 
@@ -123,12 +183,69 @@ impl TemplateApp {
         });
 
         // reset load order button
-        if self.enable_modlist &&  ui.button("⟳  Reset load order").clicked() {
-            self.reset_loadorder();
+        if self.enable_modlist {
+            ui.horizontal(|ui| {
+                if ui.button("⟳  Reset load order").clicked() {
+                    self.reset_loadorder();
+                }
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("⮪ Undo"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("⮫ Redo"))
+                    .on_hover_text("Ctrl+Y")
+                    .clicked()
+                {
+                    self.redo();
+                }
+            });
+
+            // keyboard shortcuts, text fields have their own undo
+            let has_focus = ctx.memory(|m| m.focused().is_some());
+            if !has_focus
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z))
+            {
+                self.undo();
+            }
+            if !has_focus
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y))
+            {
+                self.redo();
+            }
         }
 
+        if self.enable_modlist {
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Sort by rules")
+                    .on_hover_text("Sort the load order by the rules file")
+                    .clicked()
+                {
+                    self.sort_by_rules();
+                }
+                if ui.button("Edit rules").clicked() {
+                    self.open_rules_file();
+                }
+            });
+            for warning in &self.sort_warnings {
+                ui.colored_label(Color32::YELLOW, format!("⚠ {}", warning));
+            }
+
+            ui.separator();
+            self.profiles_view(ui);
+        }
+
+        self.modlist_diagnostics_view(ui);
+
         ui.separator();
 
+        let mut to_disable = None;
+        let mut to_enable = None;
+        let mut to_inspect = None;
         egui::ScrollArea::vertical().show(ui, |ui| {
             if self.enable_modlist {
                 egui_dnd::dnd(ui, "mod_list_dnd").show_vec(
@@ -138,6 +255,12 @@ impl TemplateApp {
                             handle.ui(ui, |ui| {
                                 ui.label("::");
                             });
+                            if !is_redmod(f) && disable_button(ui) {
+                                to_disable = Some(f.clone());
+                            }
+                            if inspect_button(ui) {
+                                to_inspect = Some(f.clone());
+                            }
                             ui.label(f.clone());
                         });
                     },
@@ -146,18 +269,58 @@ impl TemplateApp {
                 egui::Grid::new("mod_list").show(ui, |ui| {
                     let mods = &self.load_order;
                     for f in mods.iter() {
-                        ui.label(f);
+                        ui.horizontal(|ui| {
+                            if !is_redmod(f) && disable_button(ui) {
+                                to_disable = Some(f.clone());
+                            }
+                            if inspect_button(ui) {
+                                to_inspect = Some(f.clone());
+                            }
+                            ui.label(f);
+                        });
                         ui.end_row();
                     }
                 });
             }
+
+            // disabled archives stay visible
+            if !self.disabled.is_empty() {
+                ui.separator();
+                ui.collapsing(format!("Disabled ({})", self.disabled.len()), |ui| {
+                    for f in &self.disabled {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .small_button("▶")
+                                .on_hover_text("Enable archive")
+                                .clicked()
+                            {
+                                to_enable = Some(f.clone());
+                            }
+                            ui.colored_label(Color32::GRAY, f);
+                        });
+                    }
+                });
+            }
         });
+
+        if let Some(archive_name) = to_disable {
+            self.disable_archive(&archive_name);
+        }
+        if let Some(archive_name) = to_enable {
+            self.enable_archive(&archive_name);
+        }
+        if let Some(archive_name) = to_inspect {
+            self.inspect_archive(&archive_name);
+        }
     }
 
     fn reset_loadorder(&mut self) {
+        self.history.record(self.load_order.clone());
+
         // delete modlist.txt and reload
         let modlist_path = self.get_modlist_path();
         if modlist_path.exists() {
+            self.backup_modlist();
             if let Err(e) = std::fs::remove_file(&modlist_path) {
                 log::error!("Failed to delete modlist.txt: {}", e);
             }
@@ -167,6 +330,210 @@ impl TemplateApp {
         self.last_load_order = Some(self.load_order.clone());
         if self.enable_modlist {
             self.serialize_load_order();
+            self.save_profile();
+        }
+    }
+
+    /// Problems in modlist.txt with buttons to fix them
+    fn modlist_diagnostics_view(&mut self, ui: &mut egui::Ui) {
+        if self.modlist_issues.is_empty() {
+            return;
+        }
+
+        let mut fix = None;
+        let mut fix_all = false;
+        ui.separator();
+        ui.collapsing(
+            egui::RichText::new(format!("⚠ modlist.txt ({})", self.modlist_issues.len()))
+                .color(Color32::YELLOW),
+            |ui| {
+                if ui
+                    .button("Fix all")
+                    .on_hover_text("A backup of modlist.txt is kept")
+                    .clicked()
+                {
+                    fix_all = true;
+                }
+                egui::ScrollArea::vertical()
+                    .id_salt("modlist_issues")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (i, issue) in self.modlist_issues.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("Fix").clicked() {
+                                    fix = Some(i);
+                                }
+                                ui.label(issue.to_string());
+                            });
+                        }
+                    });
+            },
+        );
+
+        if fix_all {
+            self.fix_modlist(None);
+        } else if let Some(issue) = fix.and_then(|i| self.modlist_issues.get(i).cloned()) {
+            self.fix_modlist(Some(&issue));
+        }
+    }
+
+    /// Switch, duplicate, delete and compare load order profiles
+    fn profiles_view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Profile");
+            let mut selected = self.active_profile.clone();
+            egui::ComboBox::from_id_salt("active_profile")
+                .selected_text(&selected)
+                .show_ui(ui, |ui| {
+                    for name in self.profiles.keys() {
+                        ui.selectable_value(&mut selected, name.to_owned(), name);
+                    }
+                });
+            if selected != self.active_profile {
+                self.switch_profile(&selected);
+            }
+            if ui
+                .add_enabled(self.profiles.len() > 1, egui::Button::new("🗑"))
+                .on_hover_text("Delete this profile")
+                .clicked()
+            {
+                self.delete_profile();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_profile_name)
+                    .hint_text("New profile name")
+                    .desired_width(120.0),
+            );
+            if ui
+                .button("Duplicate")
+                .on_hover_text("Save the current load order as a new profile")
+                .clicked()
+            {
+                self.duplicate_profile();
+            }
+            if ui.button("Compare").clicked() {
+                self.compare_profiles =
+                    Some((self.active_profile.clone(), self.active_profile.clone()));
+            }
+        });
+    }
+
+    /// Window with earlier versions of modlist.txt
+    fn backups_view(&mut self, ctx: &egui::Context) {
+        let Some(backups) = self.backups.take() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut restore = None;
+        egui::Window::new("Restore modlist.txt")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if backups.is_empty() {
+                    ui.label(
+                        "No backups yet. modlist.txt is backed up each time it is overwritten.",
+                    );
+                    return;
+                }
+                ui.label("Backups are taken before modlist.txt is overwritten (UTC time)");
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("modlist_backups")
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            for (i, backup) in backups.iter().enumerate() {
+                                ui.collapsing(&backup.timestamp, |ui| {
+                                    for line in &backup.lines {
+                                        ui.label(line);
+                                    }
+                                });
+                                ui.label(format!("{} archives", backup.lines.len()));
+                                if ui.button("Restore").clicked() {
+                                    restore = Some(i);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if let Some(backup) = restore.and_then(|i| backups.get(i)) {
+            self.restore_backup(backup);
+            open = false;
+        }
+        if open {
+            self.backups = Some(backups);
+        }
+    }
+
+    /// Window with the differences between two profiles
+    fn compare_profiles_view(&mut self, ctx: &egui::Context) {
+        let Some((mut a, mut b)) = self.compare_profiles.take() else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Compare profiles")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for (id, selected) in [("compare_a", &mut a), ("compare_b", &mut b)] {
+                        egui::ComboBox::from_id_salt(id)
+                            .selected_text(selected.as_str())
+                            .show_ui(ui, |ui| {
+                                for name in self.profiles.keys() {
+                                    ui.selectable_value(selected, name.to_owned(), name);
+                                }
+                            });
+                    }
+                });
+                ui.separator();
+
+                let (Some(order_a), Some(order_b)) = (self.profiles.get(&a), self.profiles.get(&b))
+                else {
+                    return;
+                };
+                if !matches!(&self.profile_diffs, Some((da, db, _)) if *da == a && *db == b) {
+                    self.profile_diffs =
+                        Some((a.clone(), b.clone(), compare_load_orders(order_a, order_b)));
+                }
+                let Some((_, _, diffs)) = &self.profile_diffs else {
+                    return;
+                };
+                if diffs.is_empty() {
+                    ui.label("The load orders are the same");
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for diff in diffs {
+                        match diff {
+                            ELoadOrderDiff::Removed { name, index } => {
+                                ui.colored_label(
+                                    Color32::RED,
+                                    format!("- {} (#{})", name, index + 1),
+                                );
+                            }
+                            ELoadOrderDiff::Added { name, index } => {
+                                ui.colored_label(
+                                    Color32::GREEN,
+                                    format!("+ {} (#{})", name, index + 1),
+                                );
+                            }
+                            ELoadOrderDiff::Moved { name, from, to } => {
+                                ui.colored_label(
+                                    Color32::YELLOW,
+                                    format!("↕ {} (#{} → #{})", name, from + 1, to + 1),
+                                );
+                            }
+                        }
+                    }
+                });
+            });
+
+        if open {
+            self.compare_profiles = Some((a, b));
         }
     }
     
@@ -183,7 +550,8 @@ impl TemplateApp {
             if ui.button("...").clicked() {
                 // open file
                 if let Some(folder) = rfd::FileDialog::new().set_directory("/").pick_folder() {
-                    self.game_path = folder;
+                    // accept the game install root as well
+                    self.game_path = resolve_archive_folder(&folder);
                     // regenerate conflicts
                     self.last_load_order = None;
                 }
@@ -198,11 +566,23 @@ impl TemplateApp {
                 let _ = open::that(self.game_path.clone());
             }
         });
+        for warning in &self.install_warnings {
+            ui.colored_label(Color32::YELLOW, format!("⚠ {}", warning));
+        }
         ui.separator();
         // -------------------
         // Toolbar
         ui.horizontal(|ui| {
             ui.checkbox(&mut self.show_no_conflicts, "Show not conflicting files");
+            ui.checkbox(&mut self.hide_identical, "Hide identical duplicates")
+                .on_hover_text("Hide conflicts where all archives contain the same file content");
+            if ui
+                .checkbox(&mut self.check_vanilla, "Check vanilla overrides")
+                .on_hover_text("Read the base game archives to show which files each mod replaces. The first check takes a while.")
+                .changed()
+            {
+                self.generate_conflict_map();
+            }
             ui.label("Conflict style");
             egui::ComboBox::from_id_salt("tooltips_visuals")
                 .selected_text(format!("{:?}", &mut self.tooltips_visuals))
@@ -239,23 +619,62 @@ impl TemplateApp {
             }
         });
         ui.label(format!(
-            "Found {} conflicts across {} archives",
-            self.conflicts.len(),
+            "Found {} conflicts ({} identical duplicates) across {} archives",
+            self.report.conflicts.len(),
+            self.report.identical.len(),
             self.load_order.len()
         ));
 
+        // background scan progress
+        let mut cancel = false;
+        if let Some(scan) = &self.scan {
+            ui.horizontal(|ui| {
+                let progress = &scan.progress;
+                ui.add(
+                    egui::ProgressBar::new(progress.get_fraction())
+                        .text(format!(
+                            "{} ({}/{})",
+                            progress.get_current(),
+                            progress.get_done(),
+                            progress.get_total()
+                        ))
+                        .desired_width(ui.available_width() - 80.0),
+                );
+                cancel = ui.button("Cancel").clicked();
+            });
+        }
+        if cancel {
+            self.cancel_scan();
+        }
+
         ui.separator();
 
+        let mut to_disable = None;
+        let mut to_inspect = None;
+        let mut file_action = None;
         egui::ScrollArea::both()
             .auto_shrink([false; 2])
             .show(ui, |ui| {
                 egui::Grid::new("mod_list").num_columns(2).show(ui, |ui| {
                     for archive_name in &self.load_order {
-                        let archive_path = &self.game_path.join(archive_name);
+                        let archive_path = &get_archive_path(&self.game_path, archive_name);
                         let k = &fnv1a64_hash_path(archive_path);
-                        if let Some(mod_vm) = self.archives.get(k) {
+                        if let Some(mod_vm) = self.report.archives.get(k) {
+                            // identical duplicates are harmless
+                            let win_count = mod_vm.get_win_count(self.hide_identical);
+                            let lose_count = mod_vm.get_lose_count(self.hide_identical);
+                            let is_shown =
+                                |h: &&u64| !(self.hide_identical && self.report.is_identical(h));
+
+                            let xl_conflicts = self.report.get_xl_conflicts(archive_name);
+
+                            let show_vanilla = self.report.has_vanilla;
+                            let override_count = mod_vm.overrides.len();
+
                             // skip if no conflicts
-                            if mod_vm.loses.len() + mod_vm.wins.len() == 0 {
+                            if lose_count + win_count + xl_conflicts.len() == 0
+                                && !(show_vanilla && override_count > 0)
+                            {
                                 continue;
                             }
 
@@ -268,40 +687,48 @@ impl TemplateApp {
                                 continue;
                             }
 
-                            let filename_ext = if !self.show_no_conflicts {
-                                format!(
-                                    "{} (w: {}, l: {})",
-                                    mod_vm.file_name,
-                                    mod_vm.wins.len(),
-                                    mod_vm.loses.len()
-                                )
-                            } else {
-                                format!(
-                                    "{} (w: {}, l: {}, u: {})",
-                                    mod_vm.file_name,
-                                    mod_vm.wins.len(),
-                                    mod_vm.loses.len(),
-                                    mod_vm.get_no_conflicts().len()
-                                )
-                            };
+                            let mut filename_ext = format!(
+                                "{} (w: {}, l: {}",
+                                mod_vm.file_name, win_count, lose_count
+                            );
+                            if self.show_no_conflicts {
+                                filename_ext +=
+                                    &format!(", u: {}", mod_vm.get_no_conflicts().len());
+                            }
+                            if show_vanilla {
+                                filename_ext += &format!(", v: {}", override_count);
+                            }
+                            // files shown as raw hashes
+                            let unresolved_count =
+                                self.unresolved.get(k).copied().unwrap_or_default();
+                            if unresolved_count > 0 {
+                                filename_ext += &format!(", ?: {}", unresolved_count);
+                            }
+                            filename_ext += ")";
 
                             // column 1
                             ui.collapsing(filename_ext, |ui| {
-                                let mut header_color = if mod_vm.wins.is_empty() {
+                                let mut header_color = if win_count == 0 {
                                     ui.visuals().text_color()
                                 } else {
                                     Color32::GREEN
                                 };
                                 ui.collapsing(
-                                    egui::RichText::new(format!("winning ({})", mod_vm.wins.len()))
+                                    egui::RichText::new(format!("winning ({})", win_count))
                                         .color(header_color),
                                     |ui| {
-                                        for h in &mod_vm.wins {
+                                        for h in mod_vm.wins.iter().filter(is_shown) {
                                             // resolve hash
                                             let mut label_text = h.to_string();
                                             if let Some(file_name) = self.hashes.get(h) {
                                                 label_text = file_name.to_owned();
                                             }
+                                            if self.report.is_identical(h) {
+                                                label_text += " (identical)";
+                                            }
+                                            if self.pins.iter().any(|p| p.file == *h) {
+                                                label_text += " (pinned)";
+                                            }
 
                                             // text filter
                                             if !self.file_filter.is_empty()
@@ -312,17 +739,17 @@ impl TemplateApp {
                                                 continue;
                                             }
 
-                                            match self.tooltips_visuals {
+                                            let response = match self.tooltips_visuals {
                                                 crate::ETooltipVisuals::Tooltip => {
                                                     show_tooltip(
                                                         ui,
                                                         label_text,
                                                         h,
                                                         k,
-                                                        &self.conflicts,
-                                                        &self.archives,
+                                                        &self.report.conflicts,
+                                                        &self.report.archives,
                                                         true,
-                                                    );
+                                                    )
                                                 }
                                                 crate::ETooltipVisuals::Inline => {
                                                     show_inline(
@@ -330,10 +757,10 @@ impl TemplateApp {
                                                         label_text,
                                                         h,
                                                         k,
-                                                        &self.conflicts,
-                                                        &self.archives,
+                                                        &self.report.conflicts,
+                                                        &self.report.archives,
                                                         true
-                                                    );
+                                                    )
                                                 }
                                                 crate::ETooltipVisuals::Collapsing => {
                                                     show_dropdown_filelist(
@@ -341,30 +768,44 @@ impl TemplateApp {
                                                         label_text,
                                                         h,
                                                         k,
-                                                        &self.conflicts,
-                                                        &self.archives,
+                                                        &self.report.conflicts,
+                                                        &self.report.archives,
                                                         true,
-                                                    );
+                                                    )
                                                 }
+                                            };
+                                            if let Some(action) = self.file_context_menu(
+                                                &response,
+                                                *h,
+                                                *k,
+                                                archive_name,
+                                            ) {
+                                                file_action = Some(action);
                                             }
                                         }
                                     },
                                 );
 
-                                header_color = if mod_vm.loses.is_empty() {
+                                header_color = if lose_count == 0 {
                                     ui.visuals().text_color()
                                 } else {
                                     Color32::RED
                                 };
                                 ui.collapsing(
-                                    egui::RichText::new(format!("losing ({})", mod_vm.loses.len()))
+                                    egui::RichText::new(format!("losing ({})", lose_count))
                                         .color(header_color),
                                     |ui| {
-                                        for h in &mod_vm.loses {
+                                        for h in mod_vm.loses.iter().filter(is_shown) {
                                             let mut label_text = h.to_string();
                                             if let Some(file_name) = self.hashes.get(h) {
                                                 label_text = file_name.to_owned();
                                             }
+                                            if self.report.is_identical(h) {
+                                                label_text += " (identical)";
+                                            }
+                                            if self.pins.iter().any(|p| p.file == *h) {
+                                                label_text += " (pinned)";
+                                            }
 
                                             // text filter
                                             if !self.file_filter.is_empty()
@@ -375,17 +816,17 @@ impl TemplateApp {
                                                 continue;
                                             }
 
-                                            match self.tooltips_visuals {
+                                            let response = match self.tooltips_visuals {
                                                 crate::ETooltipVisuals::Tooltip => {
                                                     show_tooltip(
                                                         ui,
                                                         label_text,
                                                         h,
                                                         k,
-                                                        &self.conflicts,
-                                                        &self.archives,
+                                                        &self.report.conflicts,
+                                                        &self.report.archives,
                                                         false,
-                                                    );
+                                                    )
                                                 }
                                                 crate::ETooltipVisuals::Inline => {
                                                     show_inline(
@@ -393,10 +834,10 @@ impl TemplateApp {
                                                         label_text,
                                                         h,
                                                         k,
-                                                        &self.conflicts,
-                                                        &self.archives,
+                                                        &self.report.conflicts,
+                                                        &self.report.archives,
                                                         false
-                                                    );
+                                                    )
                                                 }
                                                 crate::ETooltipVisuals::Collapsing => {
                                                     show_dropdown_filelist(
@@ -404,16 +845,111 @@ impl TemplateApp {
                                                         label_text,
                                                         h,
                                                         k,
-                                                        &self.conflicts,
-                                                        &self.archives,
+                                                        &self.report.conflicts,
+                                                        &self.report.archives,
                                                         false,
-                                                    );
+                                                    )
                                                 }
+                                            };
+                                            if let Some(action) = self.file_context_menu(
+                                                &response,
+                                                *h,
+                                                *k,
+                                                archive_name,
+                                            ) {
+                                                file_action = Some(action);
                                             }
                                         }
                                     },
                                 );
 
+                                if !xl_conflicts.is_empty() {
+                                    ui.collapsing(
+                                        egui::RichText::new(format!(
+                                            "ArchiveXL ({})",
+                                            xl_conflicts.len()
+                                        ))
+                                        .color(Color32::YELLOW),
+                                        |ui| {
+                                            for (entry, archives) in &xl_conflicts {
+                                                let label_text =
+                                                    format!("{}: {}", entry.kind, entry.key);
+                                                ui.collapsing(
+                                                    egui::RichText::new(label_text)
+                                                        .color(Color32::YELLOW),
+                                                    |ui| {
+                                                        for other in archives
+                                                            .iter()
+                                                            .filter(|a| *a != archive_name)
+                                                        {
+                                                            ui.label(other);
+                                                        }
+                                                    },
+                                                );
+                                            }
+                                        },
+                                    );
+                                }
+
+                                if show_vanilla {
+                                    header_color = if override_count == 0 {
+                                        ui.visuals().text_color()
+                                    } else {
+                                        Color32::LIGHT_BLUE
+                                    };
+                                    ui.collapsing(
+                                        egui::RichText::new(format!(
+                                            "vanilla overrides ({})",
+                                            override_count
+                                        ))
+                                        .color(header_color),
+                                        |ui| {
+                                            for h in &mod_vm.overrides {
+                                                let label_text = self
+                                                    .hashes
+                                                    .get(h)
+                                                    .cloned()
+                                                    .unwrap_or_else(|| h.to_string());
+                                                if !self.file_filter.is_empty()
+                                                    && !label_text
+                                                        .to_lowercase()
+                                                        .contains(&self.file_filter.to_lowercase())
+                                                {
+                                                    continue;
+                                                }
+                                                ui.add(
+                                                    egui::Label::new(label_text)
+                                                        .wrap_mode(egui::TextWrapMode::Truncate),
+                                                );
+                                            }
+                                        },
+                                    );
+                                    let new_files = mod_vm.get_new_files();
+                                    ui.collapsing(
+                                        format!("new files ({})", new_files.len()),
+                                        |ui| {
+                                            for h in &new_files {
+                                                let label_text = self
+                                                    .hashes
+                                                    .get(h)
+                                                    .cloned()
+                                                    .unwrap_or_else(|| h.to_string());
+                                                if !self.file_filter.is_empty()
+                                                    && !label_text
+                                                        .to_lowercase()
+                                                        .contains(&self.file_filter.to_lowercase())
+                                                {
+                                                    continue;
+                                                }
+                                                ui.add(
+                                                    egui::Label::new(label_text)
+                                                        .wrap_mode(egui::TextWrapMode::Truncate),
+                                                );
+                                            }
+                                        },
+                                    );
+                                }
+
                                 if self.show_no_conflicts {
                                     ui.collapsing(
                                         format!(
@@ -421,7 +957,7 @@ impl TemplateApp {
                                             mod_vm.get_no_conflicts().len()
                                         ),
                                         |ui| {
-                                            for h in &mod_vm.get_no_conflicts() {
+                                            for h in mod_vm.get_no_conflicts() {
                                                 let mut label_text = h.to_string();
                                                 if let Some(file_name) = self.hashes.get(h) {
                                                     label_text = file_name.to_owned();
@@ -442,14 +978,20 @@ impl TemplateApp {
                                 }
                                 else {
                                      // if some files are winning add green dot
-                                    if !mod_vm.wins.is_empty() {
+                                    if win_count > 0 {
                                         ui.colored_label( Color32::GREEN, "⏺");
                                     }
                                     // if some files are losing add red dot
-                                    if !mod_vm.loses.is_empty() {
+                                    if lose_count > 0 {
                                         ui.colored_label( Color32::RED, "⏺");
                                     }
                                 }
+                                if !is_redmod(archive_name) && disable_button(ui) {
+                                    to_disable = Some(archive_name.clone());
+                                }
+                                if inspect_button(ui) {
+                                    to_inspect = Some(archive_name.clone());
+                                }
                             });
                             
                             ui.end_row();
@@ -457,7 +999,604 @@ impl TemplateApp {
                         }
                     }
                 });
+
+                // .xl files that are not named <archive>.archive.xl have no row in the load order
+                let unlinked = self.report.get_unlinked_xl_conflicts(&self.load_order);
+                if !unlinked.is_empty() {
+                    ui.collapsing(
+                        egui::RichText::new(format!("ArchiveXL files without an archive ({})", unlinked.len()))
+                            .color(Color32::YELLOW),
+                        |ui| {
+                            ui.label("These .xl files are not named <archive>.archive.xl, they are listed by their own name.");
+                            for (entry, archives) in unlinked {
+                                ui.label(format!("{}: {} ({})", entry.kind, entry.key, archives.join(", ")));
+                            }
+                        },
+                    );
+                }
+            });
+
+        if let Some(archive_name) = to_disable {
+            self.disable_archive(&archive_name);
+        }
+        if let Some(archive_name) = to_inspect {
+            self.inspect_archive(&archive_name);
+        }
+        match file_action {
+            Some(EFileAction::Extract(file_hash)) => self.extract_versions(file_hash),
+            Some(EFileAction::Compare(file_hash)) => self.compare_versions(file_hash),
+            Some(EFileAction::Patch(file_hash, archive_hash)) => {
+                self.add_patch_choice(file_hash, archive_hash)
+            }
+            Some(EFileAction::Pin(file_hash, archive_hash)) => {
+                self.pin_winner(file_hash, archive_hash)
+            }
+            Some(EFileAction::Unpin(file_hash)) => self.unpin(file_hash),
+            None => {}
+        }
+    }
+
+    /// Context menu of a winning or losing file of an archive, returns the clicked action
+    fn file_context_menu(
+        &self,
+        response: &egui::Response,
+        file_hash: u64,
+        archive_hash: u64,
+        archive_name: &str,
+    ) -> Option<EFileAction> {
+        let mut action = None;
+        response.context_menu(|ui| {
+            if ui.button("Extract all versions").clicked() {
+                action = Some(EFileAction::Extract(file_hash));
+            }
+            if ui.button("Compare versions").clicked() {
+                action = Some(EFileAction::Compare(file_hash));
+            }
+            if archive_name != PATCH_ARCHIVE_NAME
+                && ui
+                    .button("Win with this archive (conflict patch)")
+                    .clicked()
+            {
+                action = Some(EFileAction::Patch(file_hash, archive_hash));
+            }
+            // pins are met by moving archives in modlist.txt
+            if self.enable_modlist {
+                ui.separator();
+                if self
+                    .pins
+                    .iter()
+                    .any(|p| p.file == file_hash && p.archive == archive_name)
+                {
+                    if ui.button("Unpin").clicked() {
+                        action = Some(EFileAction::Unpin(file_hash));
+                    }
+                } else if ui.button("Pin: this archive must win").clicked() {
+                    action = Some(EFileAction::Pin(file_hash, archive_hash));
+                }
+            }
+            if action.is_some() {
+                ui.close_kind(UiKind::Menu);
+            }
+        });
+        action
+    }
+
+    /// Write the version of a file from every archive of its stack to a folder picked by the user
+    fn extract_versions(&self, file_hash: u64) {
+        let Some(folder) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+
+        match extract_versions(
+            &self.game_path,
+            &self.report,
+            file_hash,
+            &self.hashes,
+            &folder,
+        ) {
+            Ok(files) => {
+                log::info!("Extracted {} versions to {}", files.len(), folder.display());
+                let _ = open::that(folder);
+            }
+            Err(e) => log::error!("Failed to extract {}: {}", file_hash, e),
+        }
+    }
+
+    /// Window with the changes of each version of a file against the base version
+    fn comparison_view(&mut self, ctx: &egui::Context) {
+        let Some((name, comparison)) = &self.comparison else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new(format!("Compare: {}", name))
+            .id(egui::Id::new("compare_versions"))
+            .open(&mut open)
+            .default_size([800.0, 500.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Base: {}", comparison.base_name));
+                let overlaps = comparison.get_overlaps();
+                if overlaps.is_empty() {
+                    ui.colored_label(Color32::GREEN, "The versions change different parts of the file, they can be merged by hand");
+                } else {
+                    ui.colored_label(Color32::RED, format!("{} locations are changed by more than one version", overlaps.len()));
+                }
+                if !self.report.has_vanilla {
+                    ui.label("Enable \"Check vanilla overrides\" to compare against the base game version.");
+                }
+                ui.label("Names, arrays and structs are compared decoded, other complex values byte by byte. Exports with native data are not parsed.");
+                ui.separator();
+
+                egui::ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| {
+                    for version in &comparison.versions {
+                        ui.collapsing(format!("{} ({} changes)", version.archive_name, version.changes.len()), |ui| {
+                            if let Some(error) = &version.error {
+                                ui.colored_label(Color32::RED, error);
+                            }
+                            egui::Grid::new(("compare_grid", &version.archive_name)).striped(true).show(ui, |ui| {
+                                for change in &version.changes {
+                                    let color = if overlaps.contains(&change.path) {
+                                        Color32::RED
+                                    } else {
+                                        ui.visuals().text_color()
+                                    };
+                                    ui.colored_label(color, &change.path);
+                                    ui.label(change.kind.to_string());
+                                    ui.label(&change.old);
+                                    ui.label(&change.new);
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+
+        if !open {
+            self.comparison = None;
+        }
+    }
+
+    /// Window with the pinned winners of files and whether the load order meets them
+    fn pins_view(&mut self, ctx: &egui::Context) {
+        if !self.show_pins {
+            return;
+        }
+
+        let get_path = |file: &u64| {
+            self.hashes
+                .get(file)
+                .cloned()
+                .unwrap_or_else(|| file.to_string())
+        };
+        let mut open = true;
+        let mut to_unpin = None;
+        let mut solve = false;
+        egui::Window::new("Winner pins")
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                ui.label("Pin a winner with right click > Pin in the conflicts view, the load order is changed to meet all pins.");
+                if !self.enable_modlist {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        "⚠ Enable load order re-ordering to solve the load order, it is saved to modlist.txt.",
+                    );
+                }
+                if ui
+                    .add_enabled(self.enable_modlist && !self.pins.is_empty(), egui::Button::new("Solve load order"))
+                    .clicked()
+                {
+                    solve = true;
+                }
+
+                if !self.pin_contradiction.is_empty() {
+                    ui.separator();
+                    ui.colored_label(Color32::RED, "These pins contradict each other, remove one of them:");
+                    for pin in &self.pin_contradiction {
+                        ui.label(format!("{} must win {}", pin.archive, get_path(&pin.file)));
+                    }
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                    egui::Grid::new("winner_pins").striped(true).show(ui, |ui| {
+                        for (i, pin) in self.pins.iter().enumerate() {
+                            if ui.small_button("x").on_hover_text("Unpin").clicked() {
+                                to_unpin = Some(pin.file);
+                            }
+                            ui.label(get_path(&pin.file));
+                            ui.label(&pin.archive);
+                            match self.pin_status.get(i) {
+                                Some(EPinStatus::Satisfied) => {
+                                    ui.colored_label(Color32::GREEN, "✔ wins");
+                                }
+                                Some(EPinStatus::Violated(winner)) => {
+                                    ui.colored_label(Color32::RED, format!("✖ {} wins", winner));
+                                }
+                                Some(EPinStatus::NoConflict) => {
+                                    ui.colored_label(Color32::GRAY, "no conflict");
+                                }
+                                Some(EPinStatus::Missing) => {
+                                    ui.colored_label(Color32::RED, "the archive does not contain the file");
+                                }
+                                None => {
+                                    ui.label("");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        if let Some(file_hash) = to_unpin {
+            self.unpin(file_hash);
+        } else if solve {
+            self.solve_pins();
+        }
+        if !open {
+            self.show_pins = false;
+        }
+    }
+
+    /// Window with the files of the conflict patch archive and the archives they are taken from
+    fn patch_view(&mut self, ctx: &egui::Context) {
+        if !self.show_patch {
+            return;
+        }
+
+        let mut open = true;
+        let mut to_remove = None;
+        let mut rebuild = false;
+        egui::Window::new("Conflict patch")
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} wins these files, use right click > Win with this archive in the conflicts view to add files.", PATCH_ARCHIVE_NAME));
+                ui.horizontal(|ui| {
+                    if ui.button("Rebuild").on_hover_text("Read the files from their archives again, e.g. after a mod update").clicked() {
+                        rebuild = true;
+                    }
+                    if self.load_order.first().is_some_and(|n| n != PATCH_ARCHIVE_NAME)
+                        && self.load_order.iter().any(|n| n == PATCH_ARCHIVE_NAME)
+                    {
+                        ui.colored_label(Color32::YELLOW, "The patch is not loaded first");
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                    egui::Grid::new("patch_choices").striped(true).show(ui, |ui| {
+                        for choice in &self.patch.choices {
+                            if ui.small_button("x").on_hover_text("Remove from the patch").clicked() {
+                                to_remove = Some(choice.hash);
+                            }
+                            ui.label(&choice.path);
+                            if self.load_order.contains(&choice.archive) {
+                                ui.label(&choice.archive);
+                            } else {
+                                ui.colored_label(Color32::RED, format!("{} (missing)", choice.archive));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
             });
+
+        if let Some(file_hash) = to_remove {
+            self.remove_patch_choice(file_hash);
+        } else if rebuild {
+            self.build_patch(self.patch.clone());
+        }
+        if !open {
+            self.show_patch = false;
+        }
+    }
+
+    /// Window with the metadata of all entries of an archive
+    fn inspector_view(&mut self, ctx: &egui::Context) {
+        let Some(inspector) = &mut self.inspector else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new(format!("Inspector: {}", inspector.archive_name))
+            .id(egui::Id::new("archive_inspector"))
+            .open(&mut open)
+            .default_size([900.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Search");
+                    let mut search = inspector.get_search().to_owned();
+                    ui.text_edit_singleline(&mut search);
+                    if ui.button("x").clicked() {
+                        search.clear();
+                    }
+                    inspector.set_search(&search);
+                    ui.label(format!(
+                        "{}/{} files",
+                        inspector.get_filtered().len(),
+                        inspector.entries.len()
+                    ));
+                });
+
+                ui.collapsing("Totals per extension", |ui| {
+                    egui::Grid::new("inspector_totals")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Extension");
+                            ui.strong("Files");
+                            ui.strong("Size");
+                            ui.strong("Compressed");
+                            ui.end_row();
+                            for total in inspector.get_extension_totals() {
+                                ui.label(&total.extension);
+                                ui.label(total.count.to_string());
+                                ui.label(format_size(total.size));
+                                ui.label(format_size(total.compressed_size));
+                                ui.end_row();
+                            }
+                        });
+                });
+                ui.separator();
+
+                // click a header to sort
+                let columns = [
+                    (EEntrySortColumn::Path, "Path", 360.0),
+                    (EEntrySortColumn::Hash, "Hash", 150.0),
+                    (EEntrySortColumn::Sha1, "SHA1", 300.0),
+                    (EEntrySortColumn::Size, "Size", 80.0),
+                    (EEntrySortColumn::CompressedSize, "Compressed", 80.0),
+                    (EEntrySortColumn::Segments, "Segments", 60.0),
+                ];
+                let mut sort_by = None;
+                ui.horizontal(|ui| {
+                    for (column, name, width) in columns {
+                        let mut text = name.to_owned();
+                        if inspector.sort_column == column {
+                            text += if inspector.ascending { " ⏶" } else { " ⏷" };
+                        }
+                        if ui
+                            .add_sized([width, 18.0], egui::Button::new(text))
+                            .clicked()
+                        {
+                            sort_by = Some(column);
+                        }
+                    }
+                });
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                let filtered = inspector.get_filtered();
+                egui::ScrollArea::both()
+                    .id_salt("inspector_entries")
+                    .auto_shrink([false; 2])
+                    .show_rows(ui, row_height, filtered.len(), |ui, range| {
+                        for entry in filtered[range].iter().map(|i| &inspector.entries[*i]) {
+                            ui.horizontal(|ui| {
+                                let cells = [
+                                    entry.path.clone(),
+                                    entry.hash.to_string(),
+                                    format_sha1(&entry.sha1),
+                                    format_size(entry.size),
+                                    format_size(entry.compressed_size),
+                                    entry.segments.to_string(),
+                                ];
+                                for ((_, _, width), text) in columns.iter().zip(cells) {
+                                    ui.add_sized(
+                                        [*width, row_height],
+                                        egui::Label::new(text).truncate(),
+                                    );
+                                }
+                            });
+                        }
+                    });
+
+                if let Some(column) = sort_by {
+                    inspector.sort_by(column);
+                }
+            });
+
+        if !open {
+            self.inspector = None;
+        }
+    }
+
+    /// Window with the number of files each pair of archives shares
+    fn conflict_matrix_view(&mut self, ctx: &egui::Context) {
+        if !self.show_matrix {
+            return;
+        }
+        let hide_identical = self.hide_identical;
+        let matrix = match self.conflict_matrix.take() {
+            Some(matrix) if matrix.hide_identical == hide_identical => matrix,
+            _ => {
+                let load_order = self
+                    .load_order
+                    .iter()
+                    .map(|n| fnv1a64_hash_path(&get_archive_path(&self.game_path, n)))
+                    .collect::<Vec<_>>();
+                ConflictMatrix::new(&self.report, &load_order, hide_identical)
+            }
+        };
+        let archives = &matrix.archives;
+
+        let mut open = true;
+        let mut export_dot = false;
+        egui::Window::new("Conflict matrix")
+            .open(&mut open)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        "Number of shared files, rows win (green) or lose (red) against columns",
+                    );
+                    if ui.button("Export DOT...").clicked() {
+                        export_dot = true;
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::both()
+                    .id_salt("conflict_matrix")
+                    .max_height(ui.available_height() * 0.6)
+                    .show(ui, |ui| {
+                        egui::Grid::new("conflict_matrix_grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label("");
+                                for (i, column) in archives.iter().enumerate() {
+                                    ui.label(format!("{}", i + 1))
+                                        .on_hover_text(self.report.get_archive_name(column));
+                                }
+                                ui.end_row();
+
+                                for (r, row) in archives.iter().enumerate() {
+                                    ui.label(format!(
+                                        "{} {}",
+                                        r + 1,
+                                        self.report.get_archive_name(row)
+                                    ));
+                                    for column in archives {
+                                        let (pair, color) =
+                                            if let Some(pair) = matrix.get(*row, *column) {
+                                                (pair, Color32::GREEN)
+                                            } else if let Some(pair) = matrix.get(*column, *row) {
+                                                (pair, Color32::RED)
+                                            } else {
+                                                ui.label("");
+                                                continue;
+                                            };
+                                        let text = egui::RichText::new(
+                                            pair.get_count(hide_identical).to_string(),
+                                        )
+                                        .color(color);
+                                        if ui.small_button(text).clicked() {
+                                            self.selected_pair = Some((pair.winner, pair.loser));
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                // files of the selected pair
+                let Some(pair) = self.selected_pair.and_then(|(w, l)| matrix.get(w, l)) else {
+                    return;
+                };
+                ui.separator();
+                ui.label(format!(
+                    "{} wins against {} ({} files)",
+                    self.report.get_archive_name(&pair.winner),
+                    self.report.get_archive_name(&pair.loser),
+                    pair.get_count(hide_identical)
+                ));
+                egui::ScrollArea::vertical()
+                    .id_salt("conflict_matrix_files")
+                    .show(ui, |ui| {
+                        for h in &pair.files {
+                            let identical = self.report.is_identical(h);
+                            if hide_identical && identical {
+                                continue;
+                            }
+                            let mut label_text =
+                                self.hashes.get(h).cloned().unwrap_or_else(|| h.to_string());
+                            if identical {
+                                label_text += " (identical)";
+                            }
+                            ui.label(label_text);
+                        }
+                    });
+            });
+
+        if export_dot {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Graphviz", &["dot", "gv"])
+                .set_file_name("conflicts.dot")
+                .save_file()
+            {
+                if let Err(e) =
+                    std::fs::write(&path, to_dot(&self.report, &matrix.pairs, hide_identical))
+                {
+                    log::error!("Failed to write {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        self.conflict_matrix = Some(matrix);
+        if !open {
+            self.show_matrix = false;
+        }
+    }
+
+    /// Window with all files of all archives by depot path
+    fn file_tree_view(&mut self, ctx: &egui::Context) {
+        if !self.show_tree {
+            return;
+        }
+        self.tree_filter.hide_identical = self.hide_identical;
+        // rebuild when the report or the filter changed
+        let (filter, tree) = match self.file_tree.take() {
+            Some((filter, tree)) if filter == self.tree_filter => (filter, tree),
+            _ => (
+                self.tree_filter.clone(),
+                build_tree(&self.report, &self.hashes, &self.tree_filter),
+            ),
+        };
+
+        let mut open = true;
+        egui::Window::new("File tree")
+            .open(&mut open)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.tree_filter.conflicts_only, "Conflicts only");
+                    ui.label("Extensions");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.tree_filter.extensions)
+                            .hint_text("mesh, ent")
+                            .desired_width(120.0),
+                    );
+                });
+                ui.label(format!(
+                    "{} files, {} conflicts",
+                    tree.file_count, tree.conflict_count
+                ));
+                ui.separator();
+                egui::ScrollArea::both()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        show_folder(ui, &tree, "", &self.report);
+                    });
+            });
+
+        self.file_tree = Some((filter, tree));
+        if !open {
+            self.show_tree = false;
+        }
+    }
+
+    /// Save the current conflicts to a file picked by the user
+    fn export_report(&self, format: EExportFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.to_string(), &[format.get_extension()])
+            .set_file_name(format!("conflicts.{}", format.get_extension()))
+            .save_file()
+        else {
+            return;
+        };
+
+        let report = build_report(
+            &self.game_path,
+            &self.load_order,
+            &self.report,
+            &self.hashes,
+            self.hide_identical,
+        );
+        match export_report(&report, format, &path) {
+            Ok(_) => log::info!("Wrote report to {}", path.display()),
+            Err(e) => log::error!("Failed to write report: {}", e),
+        }
     }
 
     /// The menu bar
@@ -469,11 +1608,79 @@ impl TemplateApp {
                     let _ = open::that(self.get_modlist_path());
                     ui.close_kind(UiKind::Menu);
                 }
+                if ui
+                    .button("Import hash list...")
+                    .on_hover_text(
+                        "WolvenKit archivehashes.csv or a text file with one depot path per line",
+                    )
+                    .clicked()
+                {
+                    self.import_hashes();
+                    ui.close_kind(UiKind::Menu);
+                }
+                if ui
+                    .add_enabled(
+                        !self.user_hashes.is_empty(),
+                        egui::Button::new(format!(
+                            "Clear user hashes ({})",
+                            self.user_hashes.len()
+                        )),
+                    )
+                    .clicked()
+                {
+                    if let Err(e) = self.user_hashes.clear() {
+                        log::error!("Failed to clear user hashes: {}", e);
+                    }
+                    self.load_hashes();
+                    ui.close_kind(UiKind::Menu);
+                }
+                ui.menu_button("Export report", |ui| {
+                    for format in EExportFormat::ALL {
+                        if ui.button(format.to_string()).clicked() {
+                            self.export_report(format);
+                            ui.close_kind(UiKind::Menu);
+                        }
+                    }
+                });
+                if ui
+                    .add_enabled(
+                        self.enable_modlist,
+                        egui::Button::new("Restore modlist.txt backup..."),
+                    )
+                    .clicked()
+                {
+                    self.backups = Some(
+                        get_backup_dir(&self.game_path)
+                            .map(|dir| get_backups(&dir))
+                            .unwrap_or_default(),
+                    );
+                    ui.close_kind(UiKind::Menu);
+                }
                 ui.separator();
                 if ui.button("Quit").clicked() {
                   ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
             });
+            ui.menu_button("View", |ui| {
+                if ui
+                    .checkbox(&mut self.show_matrix, "Conflict matrix")
+                    .clicked()
+                {
+                    ui.close_kind(UiKind::Menu);
+                }
+                if ui.checkbox(&mut self.show_pins, "Winner pins").clicked() {
+                    ui.close_kind(UiKind::Menu);
+                }
+                if ui
+                    .checkbox(&mut self.show_patch, "Conflict patch")
+                    .clicked()
+                {
+                    ui.close_kind(UiKind::Menu);
+                }
+                if ui.checkbox(&mut self.show_tree, "File tree").clicked() {
+                    ui.close_kind(UiKind::Menu);
+                }
+            });
             ui.menu_button("About", |ui| {
                 ui.hyperlink("https://github.com/rfuzzo/Cyberpunk-utility/");
                 ui.separator();
@@ -500,6 +1707,90 @@ impl TemplateApp {
    
 }
 
+/// Action picked in the context menu of a file, with the file hash and the archive hash
+enum EFileAction {
+    Extract(u64),
+    Compare(u64),
+    Patch(u64, u64),
+    Pin(u64, u64),
+    Unpin(u64),
+}
+
+/// Folders and files of a depot folder, `path` makes the ids of the collapsing headers unique
+fn show_folder(ui: &mut egui::Ui, folder: &FolderNode, path: &str, report: &ConflictReport) {
+    for (name, child) in &folder.folders {
+        let child_path = format!("{}\\{}", path, name);
+        let color = if child.conflict_count > 0 {
+            Color32::YELLOW
+        } else {
+            ui.visuals().text_color()
+        };
+        egui::CollapsingHeader::new(
+            egui::RichText::new(format!(
+                "🗀 {} ({}/{})",
+                name, child.conflict_count, child.file_count
+            ))
+            .color(color),
+        )
+        .id_salt(&child_path)
+        .show(ui, |ui| {
+            show_folder(ui, child, &child_path, report);
+        });
+    }
+
+    for file in &folder.files {
+        // a single archive, nothing to compare
+        if file.archives.len() < 2 {
+            let archive_name = file
+                .archives
+                .first()
+                .map(|a| report.get_archive_name(a))
+                .unwrap_or_default();
+            ui.label(format!("🗋 {} ({})", file.name, archive_name));
+            continue;
+        }
+
+        let identical = if file.identical { " (identical)" } else { "" };
+        egui::CollapsingHeader::new(
+            egui::RichText::new(format!(
+                "🗋 {} ({} archives){}",
+                file.name,
+                file.archives.len(),
+                identical
+            ))
+            .color(Color32::YELLOW),
+        )
+        .id_salt(file.hash)
+        .show(ui, |ui| {
+            // highest priority first
+            for (i, archive_hash) in file.archives.iter().rev().enumerate() {
+                let archive_name = report.get_archive_name(archive_hash);
+                if i == 0 {
+                    ui.colored_label(Color32::GREEN, format!("✔ {} (winner)", archive_name));
+                } else {
+                    ui.colored_label(Color32::RED, format!("✖ {}", archive_name));
+                }
+            }
+        });
+    }
+}
+
+/// Button to show an archive in the inspector
+fn inspect_button(ui: &mut egui::Ui) -> bool {
+    ui.small_button("🔍")
+        .on_hover_text("Inspect archive")
+        .clicked()
+}
+
+/// Button to move an archive to the disabled folder
+fn disable_button(ui: &mut egui::Ui) -> bool {
+    ui.small_button("⏸")
+        .on_hover_text(
+            "Disable archive, it is moved to the disabled folder and left out of the load order",
+        )
+        .clicked()
+}
+
 fn get_archive_hashes_for_ui(winning: bool, archives: &[u64], key: &u64) -> Vec<u64> {
     let mut stop_skip = false;
     let mut final_names = vec![];
@@ -535,7 +1826,7 @@ fn show_inline(
     conflicts: &HashMap<u64, Vec<u64>>,
     archive_map: &HashMap<u64, ArchiveViewModel>,
     winning: bool
-) {
+) -> egui::Response {
     ui.horizontal(|ui| {
         let color = if winning {
             Color32::GREEN
@@ -555,7 +1846,9 @@ fn show_inline(
             }
 
         }
-    });
+    })
+    .response
+    .interact(egui::Sense::click())
 }
 
 
@@ -568,7 +1861,7 @@ fn show_tooltip(
     conflicts: &HashMap<u64, Vec<u64>>,
     archive_map: &HashMap<u64, ArchiveViewModel>,
     winning: bool
-) {
+) -> egui::Response {
     let color = if winning {
         Color32::GREEN
     } else {
@@ -588,7 +1881,7 @@ fn show_tooltip(
                 ui.label(archive_name);
             }
         }
-    });
+    })
 }
 
 fn show_dropdown_filelist(
@@ -599,7 +1892,7 @@ fn show_dropdown_filelist(
     conflicts: &HashMap<u64, Vec<u64>>,
     archive_map: &HashMap<u64, ArchiveViewModel>,
     winning: bool
-) {
+) -> egui::Response {
     let color = if winning {
         Color32::GREEN
     } else {
@@ -622,5 +1915,6 @@ fn show_dropdown_filelist(
                 ui.label(archive_name);
             }
         }
-    });
+    })
+    .header_response
 }
@@ -0,0 +1,120 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// number of modlist.txt backups kept per archive folder
+const MAX_BACKUPS: usize = 50;
+const BACKUP_PREFIX: &str = "modlist_";
+
+/// A copy of modlist.txt taken before it was overwritten
+#[derive(Debug, Clone)]
+pub struct ModlistBackup {
+    pub path: PathBuf,
+    /// UTC time the backup was taken, e.g. `2024-01-31_18-05-09-123`
+    pub timestamp: String,
+    /// archive names in the backup
+    pub lines: Vec<String>,
+}
+
+/// Copy modlist.txt to a timestamped file in the backup folder.
+/// Nothing is copied if modlist.txt does not exist or did not change since the last backup.
+pub fn backup_modlist(modlist_path: &Path, backup_dir: &Path) -> io::Result<Option<PathBuf>> {
+    if !modlist_path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read(modlist_path)?;
+    let backups = get_backups(backup_dir);
+    if let Some(latest) = backups.first() {
+        if fs::read(&latest.path).is_ok_and(|c| c == content) {
+            return Ok(None);
+        }
+    }
+
+    fs::create_dir_all(backup_dir)?;
+    let backup_path = backup_dir.join(format!(
+        "{}{}.txt",
+        BACKUP_PREFIX,
+        format_timestamp(SystemTime::now())
+    ));
+    fs::write(&backup_path, content)?;
+
+    // drop the oldest backups, the new one is not in the list yet
+    for old in backups.iter().skip(MAX_BACKUPS - 1) {
+        if let Err(e) = fs::remove_file(&old.path) {
+            log::error!("Failed to delete {}: {}", old.path.display(), e);
+        }
+    }
+
+    Ok(Some(backup_path))
+}
+
+/// Get all backups of a backup folder, newest first
+pub fn get_backups(backup_dir: &Path) -> Vec<ModlistBackup> {
+    let mut backups = vec![];
+    let Ok(entries) = fs::read_dir(backup_dir) else {
+        return backups;
+    };
+
+    for path in entries.flatten().map(|e| e.path()) {
+        let Some(timestamp) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_prefix(BACKUP_PREFIX))
+            .map(|s| s.to_owned())
+        else {
+            continue;
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let lines = text
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| l.to_owned())
+            .collect();
+        backups.push(ModlistBackup {
+            path,
+            timestamp,
+            lines,
+        });
+    }
+
+    // timestamps sort chronologically
+    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    backups
+}
+
+/// Format a time as a sortable UTC timestamp that can be used in file names
+pub fn format_timestamp(time: SystemTime) -> String {
+    let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = duration.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let seconds_of_day = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}-{:03}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        duration.subsec_millis()
+    )
+}
+
+/// Date from days since the unix epoch, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
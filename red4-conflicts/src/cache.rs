@@ -0,0 +1,328 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use red4lib::fnv1a64_hash_path;
+
+use crate::{
+    cr2w::{invalid_data, Reader},
+    engine::ScanProgress,
+};
+
+/// Magic of an index file
+const INDEX_MAGIC: &[u8; 4] = b"RCIX";
+/// Bump when the layout of an index file changes
+const INDEX_VERSION: u32 = 2;
+
+/// Entry list of an archive file
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveIndex {
+    /// file size in bytes when the archive was read
+    pub size: u64,
+    /// modification time in milliseconds since the unix epoch when the archive was read
+    pub modified: u64,
+    /// sorted file hashes
+    pub files: Vec<u64>,
+    /// SHA1 of the content of each file in `files`, empty for base game archives
+    pub sha1: Vec<[u8; 20]>,
+}
+
+impl ArchiveIndex {
+    /// Read the entries of an archive file, base game archives don't need the SHA1 of their files
+    pub fn read(archive_path: &Path, with_sha1: bool) -> io::Result<Self> {
+        let (size, modified) = get_file_stamp(archive_path)?;
+        let archive = red4lib::archive::open_read(archive_path)?;
+        let mut entries = archive
+            .get_entries()
+            .iter()
+            .map(|(hash, entry)| (*hash, entry.entry.sha1_hash()))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(hash, _)| *hash);
+
+        Ok(Self {
+            size,
+            modified,
+            files: entries.iter().map(|(hash, _)| *hash).collect(),
+            sha1: match with_sha1 {
+                true => entries.into_iter().map(|(_, sha1)| sha1).collect(),
+                false => vec![],
+            },
+        })
+    }
+
+    /// Sorted file hashes
+    pub fn get_files(&self) -> &[u64] {
+        &self.files
+    }
+
+    pub fn contains(&self, file_hash: &u64) -> bool {
+        self.files.binary_search(file_hash).is_ok()
+    }
+
+    /// SHA1 of the content of a file, `None` for base game archives
+    pub fn get_sha1(&self, file_hash: &u64) -> Option<&[u8; 20]> {
+        let index = self.files.binary_search(file_hash).ok()?;
+        self.sha1.get(index)
+    }
+
+    /// Write the entries together with the path of the archive
+    fn write(&self, archive_path: &Path, writer: &mut impl Write) -> io::Result<()> {
+        let path = archive_path.to_string_lossy();
+        writer.write_all(INDEX_MAGIC)?;
+        writer.write_all(&INDEX_VERSION.to_le_bytes())?;
+        writer.write_all(&self.size.to_le_bytes())?;
+        writer.write_all(&self.modified.to_le_bytes())?;
+        writer.write_all(&(path.len() as u32).to_le_bytes())?;
+        writer.write_all(path.as_bytes())?;
+        writer.write_all(&(self.files.len() as u32).to_le_bytes())?;
+        writer.write_all(&[!self.sha1.is_empty() as u8])?;
+        for hash in &self.files {
+            writer.write_all(&hash.to_le_bytes())?;
+        }
+        for sha1 in &self.sha1 {
+            writer.write_all(sha1)?;
+        }
+        Ok(())
+    }
+
+    /// Parse the entries and the path of the archive, see [`ArchiveIndex::write`]
+    fn parse(data: &[u8]) -> io::Result<(PathBuf, Self)> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(4)? != INDEX_MAGIC || reader.u32()? != INDEX_VERSION {
+            return Err(invalid_data("Outdated index file"));
+        }
+        let size = reader.u64()?;
+        let modified = reader.u64()?;
+        let path_len = reader.u32()? as usize;
+        let path = String::from_utf8_lossy(reader.bytes(path_len)?).to_string();
+        let count = reader.u32()? as usize;
+        let with_sha1 = reader.u8()? != 0;
+        let files = (0..count)
+            .map(|_| reader.u64())
+            .collect::<io::Result<Vec<_>>>()?;
+        let sha1 = match with_sha1 {
+            true => reader
+                .bytes(count * 20)?
+                .chunks_exact(20)
+                .map(|c| c.try_into().unwrap())
+                .collect(),
+            false => vec![],
+        };
+
+        Ok((
+            PathBuf::from(path),
+            Self {
+                size,
+                modified,
+                files,
+                sha1,
+            },
+        ))
+    }
+}
+
+/// Archive entry lists keyed by archive path, optionally persisted to a folder with a binary
+/// file per archive. The folder is read on the first update, so it can be created on the UI thread.
+/// Archives are only read again when their size or modification time changes.
+#[derive(Debug, Default)]
+pub struct IndexCache {
+    archives: HashMap<PathBuf, Arc<ArchiveIndex>>,
+
+    /// folder to persist the cache to
+    dir: Option<PathBuf>,
+    /// the folder was read
+    loaded: bool,
+    /// archives that were read since the last save
+    changed: HashSet<PathBuf>,
+}
+
+impl IndexCache {
+    /// Create a cache that is persisted to a folder
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: Some(dir.to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    /// Read the persisted archives once, files of deleted archives and outdated files are removed
+    fn load(&mut self) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for path in entries.flatten().map(|e| e.path()) {
+            match fs::read(&path).and_then(|data| ArchiveIndex::parse(&data)) {
+                Ok((archive_path, index)) if archive_path.exists() => {
+                    self.archives.insert(archive_path, Arc::new(index));
+                }
+                Ok(_) => {
+                    let _ = fs::remove_file(&path);
+                }
+                Err(e) => {
+                    log::info!("Discarding index file {}: {}", path.display(), e);
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+    }
+
+    /// Write the archives that were read since the last save
+    pub fn save(&mut self) {
+        let Some(dir) = &self.dir else {
+            self.changed.clear();
+            return;
+        };
+        if self.changed.is_empty() {
+            return;
+        }
+        if let Err(e) = fs::create_dir_all(dir) {
+            log::error!("Failed to create index cache {}: {}", dir.display(), e);
+            return;
+        }
+
+        for archive_path in self.changed.drain() {
+            let Some(index) = self.archives.get(&archive_path) else {
+                continue;
+            };
+            let path = dir.join(format!("{:016x}.bin", fnv1a64_hash_path(&archive_path)));
+            let result = File::create(&path).and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                index.write(&archive_path, &mut writer)?;
+                writer.flush()
+            });
+            if let Err(e) = result {
+                log::error!("Failed to write index cache {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Drop all archives and the persisted files
+    pub fn clear(&mut self) {
+        self.archives.clear();
+        self.changed.clear();
+        self.loaded = true;
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    /// Get the entries of an archive if it did not change on disk since it was read
+    pub fn get(&self, archive_path: &Path) -> Option<&Arc<ArchiveIndex>> {
+        let (size, modified) = get_file_stamp(archive_path).ok()?;
+        self.archives
+            .get(archive_path)
+            .filter(|a| a.size == size && a.modified == modified)
+    }
+
+    /// Read all archives that changed on disk, on as many threads as there are cores.
+    /// Base game archives are read without the SHA1 of their files.
+    pub fn update(
+        &mut self,
+        archive_paths: &[PathBuf],
+        vanilla_paths: &[PathBuf],
+        progress: &ScanProgress,
+    ) {
+        self.load();
+
+        let changed = archive_paths
+            .iter()
+            .map(|p| (p, true))
+            .chain(vanilla_paths.iter().map(|p| (p, false)))
+            .filter(|(p, _)| self.get(p).is_none())
+            .collect::<Vec<_>>();
+        progress.start(changed.len());
+        if changed.is_empty() {
+            return;
+        }
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![]);
+        let threads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(changed.len());
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| {
+                    while !progress.is_cancelled() {
+                        let Some((path, with_sha1)) =
+                            changed.get(next.fetch_add(1, Ordering::Relaxed))
+                        else {
+                            break;
+                        };
+
+                        progress.set_current(&path.display().to_string());
+                        log::info!("parsing {}", path.display());
+                        match ArchiveIndex::read(path, *with_sha1) {
+                            Ok(index) => results.lock().unwrap().push(((*path).clone(), index)),
+                            Err(e) => log::error!("Failed to read {}: {}", path.display(), e),
+                        }
+                        progress.advance();
+                    }
+                });
+            }
+        });
+
+        // archives read before a cancel are still valid
+        for (path, index) in results.into_inner().unwrap() {
+            self.changed.insert(path.clone());
+            self.archives.insert(path, Arc::new(index));
+        }
+    }
+}
+
+/// Size and modification time of a file
+pub(crate) fn get_file_stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    Ok((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_file_round_trip() {
+        let archive_path = Path::new("archive/pc/mod/a.archive");
+        for sha1 in [vec![[1; 20], [2; 20]], vec![]] {
+            let index = ArchiveIndex {
+                size: 10,
+                modified: 20,
+                files: vec![3, 4],
+                sha1,
+            };
+            let mut data = vec![];
+            index.write(archive_path, &mut data).unwrap();
+
+            let (path, parsed) = ArchiveIndex::parse(&data).unwrap();
+            assert_eq!(path, archive_path);
+            assert_eq!((parsed.size, parsed.modified), (10, 20));
+            assert_eq!(parsed.files, index.files);
+            assert_eq!(parsed.sha1, index.sha1);
+            assert_eq!(parsed.get_sha1(&4), index.sha1.get(1));
+            assert!(ArchiveIndex::parse(&data[..data.len() - 1]).is_err());
+        }
+    }
+}
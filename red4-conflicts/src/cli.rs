@@ -0,0 +1,232 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use red4lib::fnv1a64_hash_path;
+
+use crate::{get_archive_path, get_load_order, xl::XlEntry, ConflictEngine, ConflictReport};
+
+/// Conflict summary of a single archive
+#[derive(Debug, serde::Serialize)]
+pub struct ArchiveSummary {
+    pub file_name: String,
+    pub wins: usize,
+    pub loses: usize,
+    pub unique: usize,
+    /// files that replace base game files, only set if base game archives were indexed
+    pub vanilla_overrides: usize,
+    /// files whose path is not in the hash lists
+    pub unresolved: usize,
+    pub files: usize,
+}
+
+/// A file that is contained in more than one archive
+#[derive(Debug, serde::Serialize)]
+pub struct FileConflict {
+    pub hash: u64,
+    pub name: String,
+    /// all archives contain the same file content
+    pub identical: bool,
+    /// archive names from lowest to highest priority, the last one wins
+    pub archives: Vec<String>,
+}
+
+/// An ArchiveXL registration that is made by more than one archive
+#[derive(Debug, serde::Serialize)]
+pub struct XlConflict {
+    #[serde(flatten)]
+    pub entry: XlEntry,
+    pub archives: Vec<String>,
+}
+
+/// Result of a headless conflict scan
+#[derive(Debug, serde::Serialize)]
+pub struct ScanReport {
+    pub game_path: PathBuf,
+    pub load_order: Vec<String>,
+    pub archives: Vec<ArchiveSummary>,
+    pub conflicts: Vec<FileConflict>,
+    pub xl_conflicts: Vec<XlConflict>,
+}
+
+/// Run the load order and conflict computation without a window, file hashes are resolved with `hashes`.
+/// Identical duplicates are left out of the report if `hide_identical` is set.
+pub fn scan(
+    engine: &mut ConflictEngine,
+    game_path: &Path,
+    hashes: &HashMap<u64, String>,
+    hide_identical: bool,
+) -> ScanReport {
+    let load_order = get_load_order(game_path);
+    let report = engine.generate_conflict_map(game_path, &load_order);
+    build_report(game_path, &load_order, &report, hashes, hide_identical)
+}
+
+/// Summarize a conflict report, file hashes are resolved with `hashes`.
+/// Identical duplicates are left out if `hide_identical` is set.
+pub fn build_report(
+    game_path: &Path,
+    load_order: &[String],
+    report: &ConflictReport,
+    hashes: &HashMap<u64, String>,
+    hide_identical: bool,
+) -> ScanReport {
+    let mut archives = vec![];
+    for archive_name in load_order {
+        let archive_hash = fnv1a64_hash_path(&get_archive_path(game_path, archive_name));
+        if let Some(vm) = report.get_archive(&archive_hash) {
+            archives.push(ArchiveSummary {
+                file_name: vm.file_name.to_owned(),
+                wins: vm.get_win_count(hide_identical),
+                loses: vm.get_lose_count(hide_identical),
+                unique: vm.get_no_conflicts().len(),
+                vanilla_overrides: vm.overrides.len(),
+                unresolved: vm.get_unresolved_count(hashes),
+                files: vm.files.len(),
+            });
+        }
+    }
+
+    let mut conflicts = vec![];
+    for (hash, archive_hashes) in &report.conflicts {
+        let identical = report.is_identical(hash);
+        if hide_identical && identical {
+            continue;
+        }
+
+        let name = hashes
+            .get(hash)
+            .cloned()
+            .unwrap_or_else(|| hash.to_string());
+        conflicts.push(FileConflict {
+            hash: *hash,
+            name,
+            identical,
+            archives: archive_hashes
+                .iter()
+                .map(|h| report.get_archive_name(h))
+                .collect(),
+        });
+    }
+    conflicts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut xl_conflicts = report
+        .xl_conflicts
+        .iter()
+        .map(|(entry, archives)| XlConflict {
+            entry: entry.clone(),
+            archives: archives.clone(),
+        })
+        .collect::<Vec<_>>();
+    xl_conflicts.sort_by(|a, b| a.entry.cmp(&b.entry));
+
+    ScanReport {
+        game_path: game_path.to_path_buf(),
+        load_order: load_order.to_vec(),
+        archives,
+        conflicts,
+        xl_conflicts,
+    }
+}
+
+/// Print a winners/losers summary to the console
+pub fn print_report(report: &ScanReport) {
+    println!(
+        "Found {} conflicts and {} ArchiveXL conflicts across {} archives",
+        report.conflicts.len(),
+        report.xl_conflicts.len(),
+        report.load_order.len()
+    );
+
+    // group the conflicts by archive once
+    let mut conflicts: HashMap<&str, Vec<&FileConflict>> = HashMap::default();
+    for conflict in &report.conflicts {
+        for archive in &conflict.archives {
+            conflicts.entry(archive).or_default().push(conflict);
+        }
+    }
+    let mut xl_conflicts: HashMap<&str, Vec<&XlConflict>> = HashMap::default();
+    for conflict in &report.xl_conflicts {
+        for archive in &conflict.archives {
+            xl_conflicts.entry(archive).or_default().push(conflict);
+        }
+    }
+
+    for archive in &report.archives {
+        let file_conflicts = conflicts
+            .get(archive.file_name.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let xl_conflicts = xl_conflicts
+            .get(archive.file_name.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if archive.wins + archive.loses + xl_conflicts.len() == 0 {
+            continue;
+        }
+
+        println!();
+        println!(
+            "{} (w: {}, l: {}, u: {}, v: {}, ?: {})",
+            archive.file_name,
+            archive.wins,
+            archive.loses,
+            archive.unique,
+            archive.vanilla_overrides,
+            archive.unresolved
+        );
+
+        for conflict in file_conflicts {
+            let Some(winner) = conflict.archives.last() else {
+                continue;
+            };
+
+            let identical = if conflict.identical {
+                " (identical)"
+            } else {
+                ""
+            };
+            if winner == &archive.file_name {
+                println!("\twins:  {}{}", conflict.name, identical);
+            } else {
+                println!("\tloses: {} (to {}){}", conflict.name, winner, identical);
+            }
+        }
+
+        for conflict in xl_conflicts {
+            let others = conflict
+                .archives
+                .iter()
+                .filter(|a| *a != &archive.file_name)
+                .cloned()
+                .collect::<Vec<_>>();
+            println!(
+                "\txl:    {}: {} (also in {})",
+                conflict.entry.kind,
+                conflict.entry.key,
+                others.join(", ")
+            );
+        }
+    }
+
+    // .xl files that are not named <archive>.archive.xl are listed by their own name
+    let load_order = report.load_order.iter().collect::<HashSet<_>>();
+    let unlinked = report
+        .xl_conflicts
+        .iter()
+        .filter(|c| c.archives.iter().any(|a| !load_order.contains(a)))
+        .collect::<Vec<_>>();
+    if !unlinked.is_empty() {
+        println!();
+        println!("ArchiveXL files without an archive");
+        for conflict in unlinked {
+            println!(
+                "\txl:    {}: {} ({})",
+                conflict.entry.kind,
+                conflict.entry.key,
+                conflict.archives.join(", ")
+            );
+        }
+    }
+}
@@ -0,0 +1,528 @@
+use std::io;
+
+const MAGIC: &[u8; 4] = b"CR2W";
+const TABLE_COUNT: usize = 10;
+
+/// values longer than this are shortened for display
+const MAX_VALUE_BYTES: usize = 32;
+const MAX_VALUE_CHARS: usize = 200;
+
+/// A serialized property of an export
+#[derive(Debug, Clone)]
+pub struct Cr2wProperty {
+    pub name: String,
+    pub type_name: String,
+    pub value: Vec<u8>,
+}
+
+/// An export (chunk) of a CR2W file
+#[derive(Debug, Clone)]
+pub struct Cr2wExport {
+    pub class_name: String,
+    /// index of the parent export plus one, 0 if there is none
+    pub parent: u32,
+    pub properties: Vec<Cr2wProperty>,
+    /// data that could not be read as properties
+    pub unparsed: Vec<u8>,
+}
+
+/// The structure of a CR2W file: the header tables and the property list of each export.
+/// Property values are kept as raw bytes, exports with native serialization stay unparsed.
+#[derive(Debug, Clone, Default)]
+pub struct Cr2wFile {
+    pub version: u32,
+    pub names: Vec<String>,
+    /// depot paths of the referenced files
+    pub imports: Vec<String>,
+    pub exports: Vec<Cr2wExport>,
+}
+
+impl Cr2wFile {
+    /// Read the header tables and the exports of a CR2W file
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(4)? != MAGIC {
+            return Err(invalid_data("Not a CR2W file"));
+        }
+        let version = reader.u32()?;
+        let _flags = reader.u32()?;
+        let _timestamp = reader.u64()?;
+        let _build_version = reader.u32()?;
+        let _objects_end = reader.u32()?;
+        let _buffers_end = reader.u32()?;
+        let _crc32 = reader.u32()?;
+        let _num_chunks = reader.u32()?;
+
+        // offset and item count of each table
+        let mut tables = [(0usize, 0usize); TABLE_COUNT];
+        for table in tables.iter_mut() {
+            let offset = reader.u32()? as usize;
+            let count = reader.u32()? as usize;
+            let _crc32 = reader.u32()?;
+            *table = (offset, count);
+        }
+
+        // the string table is a blob of null terminated strings, its item count is the size in bytes
+        let (strings_offset, strings_size) = tables[0];
+        let strings = Reader::new(data)
+            .seek(strings_offset)?
+            .bytes(strings_size)?;
+
+        let mut names = vec![];
+        let mut table = Reader::new(data).seek(tables[1].0)?;
+        for _ in 0..tables[1].1 {
+            let offset = table.u32()? as usize;
+            let _hash = table.u32()?;
+            names.push(read_string(strings, offset));
+        }
+
+        let mut imports = vec![];
+        let mut table = Reader::new(data).seek(tables[2].0)?;
+        for _ in 0..tables[2].1 {
+            let offset = table.u32()? as usize;
+            let _class_name = table.u16()?;
+            let _flags = table.u16()?;
+            imports.push(read_string(strings, offset));
+        }
+
+        let mut exports = vec![];
+        let mut table = Reader::new(data).seek(tables[4].0)?;
+        for _ in 0..tables[4].1 {
+            let class_name = table.u16()? as usize;
+            let _object_flags = table.u16()?;
+            let parent = table.u32()?;
+            let data_size = table.u32()? as usize;
+            let data_offset = table.u32()? as usize;
+            let _template = table.u32()?;
+            let _crc32 = table.u32()?;
+
+            let export_data = Reader::new(data).seek(data_offset)?.bytes(data_size)?;
+            let (properties, unparsed) = read_properties(export_data, &names);
+            exports.push(Cr2wExport {
+                class_name: get_name(&names, class_name),
+                parent,
+                properties,
+                unparsed,
+            });
+        }
+
+        Ok(Self {
+            version,
+            names,
+            imports,
+            exports,
+        })
+    }
+
+    /// Readable value of a property, unknown types are shown as hex
+    pub fn format_value(&self, property: &Cr2wProperty) -> String {
+        match self.decode_value(property) {
+            Some(text) if text.chars().count() > MAX_VALUE_CHARS => {
+                let text = text.chars().take(MAX_VALUE_CHARS).collect::<String>();
+                format!("{} ...", text)
+            }
+            Some(text) => text,
+            None => format_bytes(&property.value),
+        }
+    }
+
+    /// Value of a property with names resolved, `None` if the type can not be read.
+    /// Name indices differ between files, so values of two files can only be compared decoded.
+    pub fn decode_value(&self, property: &Cr2wProperty) -> Option<String> {
+        let mut reader = Reader::new(&property.value);
+        let value = self.read_value(&property.type_name, &mut reader).ok()?;
+        (reader.pos == property.value.len()).then_some(value)
+    }
+
+    fn read_value(&self, type_name: &str, reader: &mut Reader<'_>) -> io::Result<String> {
+        if let Some(item_type) = type_name.strip_prefix("array:") {
+            let count = reader.u32()?;
+            let mut items = vec![];
+            for _ in 0..count {
+                items.push(self.read_value(item_type, reader)?);
+            }
+            return Ok(format!("[{}]", items.join(", ")));
+        }
+        // exports are matched by their index, so handles are compared as they are
+        if type_name.starts_with("handle:") || type_name.starts_with("whandle:") {
+            return Ok(format!("#{}", reader.u32()? as i32));
+        }
+
+        let value = match type_name {
+            "Bool" => (reader.u8()? != 0).to_string(),
+            "Uint8" => reader.u8()?.to_string(),
+            "Int8" => (reader.u8()? as i8).to_string(),
+            "Uint16" => reader.u16()?.to_string(),
+            "Int16" => (reader.u16()? as i16).to_string(),
+            "Uint32" => reader.u32()?.to_string(),
+            "Int32" => (reader.u32()? as i32).to_string(),
+            "Uint64" => reader.u64()?.to_string(),
+            "Int64" => (reader.u64()? as i64).to_string(),
+            "Float" => f32::from_bits(reader.u32()?).to_string(),
+            "Double" => f64::from_bits(reader.u64()?).to_string(),
+            "CName" => get_name(&self.names, reader.u16()? as usize),
+            // other types are read as structs, which are serialized like the properties of an export
+            _ => {
+                if reader.u8()? != 0 {
+                    return Err(invalid_data("Unknown type"));
+                }
+                let mut fields = vec![];
+                loop {
+                    let name = reader.u16()?;
+                    if name == 0 {
+                        break;
+                    }
+                    let field_type = get_name(&self.names, reader.u16()? as usize);
+                    let size = reader
+                        .u32()?
+                        .checked_sub(4)
+                        .ok_or_else(|| invalid_data("Invalid size"))?
+                        as usize;
+                    let mut field = Reader::new(reader.bytes(size)?);
+                    let value = self.read_value(&field_type, &mut field)?;
+                    if field.pos != size {
+                        return Err(invalid_data("Invalid size"));
+                    }
+                    fields.push(format!(
+                        "{}: {}",
+                        get_name(&self.names, name as usize),
+                        value
+                    ));
+                }
+                format!("{{{}}}", fields.join(", "))
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Read the properties of an export, returns the properties and the data after them
+fn read_properties(data: &[u8], names: &[String]) -> (Vec<Cr2wProperty>, Vec<u8>) {
+    let mut reader = Reader::new(data);
+    let mut properties = vec![];
+    // properties start with a zero byte, other exports use native serialization
+    if !matches!(reader.u8(), Ok(0)) {
+        return (properties, data.to_vec());
+    }
+
+    loop {
+        let start = reader.pos;
+        let Ok(name) = reader.u16() else {
+            return (properties, data[start..].to_vec());
+        };
+        if name == 0 {
+            break;
+        }
+        let property = reader.u16().and_then(|type_name| {
+            // the size includes the size field
+            let size = reader
+                .u32()?
+                .checked_sub(4)
+                .ok_or_else(|| invalid_data("Invalid size"))?;
+            Ok(Cr2wProperty {
+                name: get_name(names, name as usize),
+                type_name: get_name(names, type_name as usize),
+                value: reader.bytes(size as usize)?.to_vec(),
+            })
+        });
+        match property {
+            Ok(property) => properties.push(property),
+            Err(_) => return (properties, data[start..].to_vec()),
+        }
+    }
+    (properties, data[reader.pos..].to_vec())
+}
+
+fn get_name(names: &[String], index: usize) -> String {
+    names
+        .get(index)
+        .cloned()
+        .unwrap_or_else(|| format!("<name {}>", index))
+}
+
+fn read_string(strings: &[u8], offset: usize) -> String {
+    let bytes = strings.get(offset..).unwrap_or_default();
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+/// Hex string of the first bytes of a value
+pub fn format_bytes(bytes: &[u8]) -> String {
+    let mut text = bytes
+        .iter()
+        .take(MAX_VALUE_BYTES)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if bytes.len() > MAX_VALUE_BYTES {
+        text += &format!(" ... ({} bytes)", bytes.len());
+    }
+    text
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// Little endian reader over a byte slice
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn seek(mut self, pos: usize) -> io::Result<Self> {
+        if pos > self.data.len() {
+            return Err(invalid_data("Offset out of range"));
+        }
+        self.pos = pos;
+        Ok(self)
+    }
+
+    pub(crate) fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| invalid_data("Unexpected end of file"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> io::Result<u32> {
+        let mut buffer = [0; 4];
+        buffer.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<u64> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER_SIZE: usize = 40 + TABLE_COUNT * 12;
+
+    /// CR2W file with the given names, imports and exports of (class name, export data)
+    fn build(names: &[&str], imports: &[&str], exports: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut strings = vec![];
+        let mut add_string = |s: &str| {
+            let offset = strings.len() as u32;
+            strings.extend_from_slice(s.as_bytes());
+            strings.push(0);
+            offset
+        };
+        let name_offsets = names.iter().map(|n| add_string(n)).collect::<Vec<_>>();
+        let import_offsets = imports.iter().map(|i| add_string(i)).collect::<Vec<_>>();
+
+        let names_offset = HEADER_SIZE + strings.len();
+        let imports_offset = names_offset + names.len() * 8;
+        let exports_offset = imports_offset + imports.len() * 8;
+        let mut data_offset = exports_offset + exports.len() * 24;
+
+        let mut tables = [(0u32, 0u32); TABLE_COUNT];
+        tables[0] = (HEADER_SIZE as u32, strings.len() as u32);
+        tables[1] = (names_offset as u32, names.len() as u32);
+        tables[2] = (imports_offset as u32, imports.len() as u32);
+        tables[4] = (exports_offset as u32, exports.len() as u32);
+
+        let mut file = MAGIC.to_vec();
+        file.extend(195u32.to_le_bytes());
+        file.extend([0u8; 32]);
+        for (offset, count) in tables {
+            file.extend(offset.to_le_bytes());
+            file.extend(count.to_le_bytes());
+            file.extend(0u32.to_le_bytes());
+        }
+        file.extend(strings);
+        for offset in name_offsets {
+            file.extend(offset.to_le_bytes());
+            file.extend(0u32.to_le_bytes());
+        }
+        for offset in import_offsets {
+            file.extend(offset.to_le_bytes());
+            file.extend(0u32.to_le_bytes());
+        }
+        for (class_name, data) in exports {
+            file.extend(class_name.to_le_bytes());
+            file.extend(0u16.to_le_bytes());
+            file.extend(0u32.to_le_bytes());
+            file.extend((data.len() as u32).to_le_bytes());
+            file.extend((data_offset as u32).to_le_bytes());
+            file.extend([0u8; 8]);
+            data_offset += data.len();
+        }
+        for (_, data) in exports {
+            file.extend(data);
+        }
+        file
+    }
+
+    /// property list of (name, type, value) followed by native data
+    fn properties(properties: &[(u16, u16, &[u8])], native: &[u8]) -> Vec<u8> {
+        let mut data = vec![0];
+        for (name, type_name, value) in properties {
+            data.extend(name.to_le_bytes());
+            data.extend(type_name.to_le_bytes());
+            data.extend((value.len() as u32 + 4).to_le_bytes());
+            data.extend(*value);
+        }
+        data.extend(0u16.to_le_bytes());
+        data.extend(native);
+        data
+    }
+
+    #[test]
+    fn parse() {
+        let names = [
+            "None",
+            "entEntityTemplate",
+            "visible",
+            "Bool",
+            "appearance",
+            "CName",
+            "count",
+            "Uint32",
+            "default",
+        ];
+        let data = build(
+            &names,
+            &["base\\characters\\npc.ent"],
+            &[
+                (
+                    1,
+                    properties(
+                        &[
+                            (2, 3, &[1]),
+                            (4, 5, &8u16.to_le_bytes()),
+                            (6, 7, &7u32.to_le_bytes()),
+                        ],
+                        &[0xaa, 0xbb],
+                    ),
+                ),
+                (1, vec![0xff, 0x00]),
+            ],
+        );
+
+        let file = Cr2wFile::parse(&data).unwrap();
+        assert_eq!(file.version, 195);
+        assert_eq!(file.names, names);
+        assert_eq!(file.imports, ["base\\characters\\npc.ent"]);
+        assert_eq!(file.exports.len(), 2);
+
+        let export = &file.exports[0];
+        assert_eq!(export.class_name, "entEntityTemplate");
+        assert_eq!(
+            export
+                .properties
+                .iter()
+                .map(|p| (p.name.as_str(), p.type_name.as_str(), file.format_value(p)))
+                .collect::<Vec<_>>(),
+            [
+                ("visible", "Bool", "true".to_owned()),
+                ("appearance", "CName", "default".to_owned()),
+                ("count", "Uint32", "7".to_owned()),
+            ]
+        );
+        assert_eq!(export.unparsed, [0xaa, 0xbb]);
+
+        // native serialization
+        assert!(file.exports[1].properties.is_empty());
+        assert_eq!(file.exports[1].unparsed, [0xff, 0x00]);
+    }
+
+    #[test]
+    fn decode_names_in_arrays_and_structs() {
+        // a struct with a name and an array of names, stored with different name tables
+        let value = |names: [u16; 5]| {
+            let [field, cname, array, appearance, other] = names;
+            let mut items = 2u32.to_le_bytes().to_vec();
+            items.extend(appearance.to_le_bytes());
+            items.extend(other.to_le_bytes());
+            // "names" follows "name" in both tables
+            properties(
+                &[
+                    (field, cname, &appearance.to_le_bytes()),
+                    (field + 1, array, &items),
+                ],
+                &[],
+            )
+        };
+        let file = |names: &[&str], indices: [u16; 5], type_name: u16| {
+            let data = build(
+                names,
+                &[],
+                &[(1, properties(&[(2, type_name, &value(indices))], &[]))],
+            );
+            Cr2wFile::parse(&data).unwrap()
+        };
+
+        let a = file(
+            &[
+                "None",
+                "entEntityTemplate",
+                "data",
+                "Settings",
+                "name",
+                "names",
+                "CName",
+                "array:CName",
+                "default",
+                "red",
+            ],
+            [4, 6, 7, 8, 9],
+            3,
+        );
+        let b = file(
+            &[
+                "None",
+                "entEntityTemplate",
+                "data",
+                "red",
+                "default",
+                "array:CName",
+                "CName",
+                "name",
+                "names",
+                "Settings",
+            ],
+            [7, 6, 5, 4, 3],
+            9,
+        );
+        let a_value = a.decode_value(&a.exports[0].properties[0]).unwrap();
+        assert_eq!(a_value, "{name: default, names: [default, red]}");
+        assert_eq!(
+            b.decode_value(&b.exports[0].properties[0]).unwrap(),
+            a_value
+        );
+        assert_ne!(
+            a.exports[0].properties[0].value,
+            b.exports[0].properties[0].value
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(Cr2wFile::parse(b"RDTS").is_err());
+        assert!(Cr2wFile::parse(&build(&[], &[], &[])[..100]).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, io,
+};
+
+use crate::{
+    cr2w::{format_bytes, Cr2wExport, Cr2wFile, Cr2wProperty},
+    extract::FileVersion,
+};
+
+/// Kind of a difference between two versions of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for EChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EChangeKind::Added => write!(f, "added"),
+            EChangeKind::Removed => write!(f, "removed"),
+            EChangeKind::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// A difference between the base version of a file and another version
+#[derive(Debug, Clone)]
+pub struct Cr2wChange {
+    /// location in the file, e.g. `#3 entEntityTemplate.components`
+    pub path: String,
+    pub kind: EChangeKind,
+    pub old: String,
+    pub new: String,
+}
+
+/// Changes of one version of a file against the base version
+#[derive(Debug, Clone)]
+pub struct VersionDiff {
+    pub archive_name: String,
+    pub changes: Vec<Cr2wChange>,
+    /// set if the version could not be read
+    pub error: Option<String>,
+}
+
+/// Comparison of the versions of a file against a base version
+#[derive(Debug, Clone)]
+pub struct Cr2wComparison {
+    pub base_name: String,
+    pub versions: Vec<VersionDiff>,
+}
+
+impl Cr2wComparison {
+    /// Locations that are changed by more than one version.
+    /// If there are none, the versions change different parts and can be merged by hand.
+    pub fn get_overlaps(&self) -> BTreeSet<String> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::default();
+        for version in &self.versions {
+            let paths = version
+                .changes
+                .iter()
+                .map(|c| c.path.as_str())
+                .collect::<BTreeSet<_>>();
+            for path in paths {
+                *counts.entry(path).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(path, _)| path.to_owned())
+            .collect()
+    }
+}
+
+/// Compare versions of a file against a base version, e.g. the base game version or the one with the lowest priority
+pub fn compare_versions(
+    base: &FileVersion,
+    versions: &[FileVersion],
+) -> io::Result<Cr2wComparison> {
+    let base_file = Cr2wFile::parse(&base.data)?;
+    let versions = versions
+        .iter()
+        .map(|version| match Cr2wFile::parse(&version.data) {
+            Ok(file) => VersionDiff {
+                archive_name: version.archive_name.clone(),
+                changes: diff_files(&base_file, &file),
+                error: None,
+            },
+            Err(e) => VersionDiff {
+                archive_name: version.archive_name.clone(),
+                changes: vec![],
+                error: Some(e.to_string()),
+            },
+        })
+        .collect();
+
+    Ok(Cr2wComparison {
+        base_name: base.archive_name.clone(),
+        versions,
+    })
+}
+
+/// Differences of the imports, exports and property values of two CR2W files
+pub fn diff_files(base: &Cr2wFile, other: &Cr2wFile) -> Vec<Cr2wChange> {
+    let mut changes = vec![];
+
+    let base_imports = base.imports.iter().collect::<BTreeSet<_>>();
+    let other_imports = other.imports.iter().collect::<BTreeSet<_>>();
+    for import in base_imports.difference(&other_imports) {
+        changes.push(change(
+            &format!("imports/{}", import),
+            EChangeKind::Removed,
+            import,
+            "",
+        ));
+    }
+    for import in other_imports.difference(&base_imports) {
+        changes.push(change(
+            &format!("imports/{}", import),
+            EChangeKind::Added,
+            "",
+            import,
+        ));
+    }
+
+    // exports are matched by their index
+    for i in 0..base.exports.len().max(other.exports.len()) {
+        match (base.exports.get(i), other.exports.get(i)) {
+            (Some(a), Some(b)) if a.class_name != b.class_name => {
+                changes.push(change(
+                    &get_export_path(i, a),
+                    EChangeKind::Changed,
+                    &a.class_name,
+                    &b.class_name,
+                ));
+            }
+            (Some(a), Some(b)) => diff_exports(base, a, other, b, i, &mut changes),
+            (Some(a), None) => {
+                changes.push(change(
+                    &get_export_path(i, a),
+                    EChangeKind::Removed,
+                    "export",
+                    "",
+                ));
+            }
+            (None, Some(b)) => {
+                changes.push(change(
+                    &get_export_path(i, b),
+                    EChangeKind::Added,
+                    "",
+                    "export",
+                ));
+            }
+            (None, None) => {}
+        }
+    }
+    changes
+}
+
+fn diff_exports(
+    base_file: &Cr2wFile,
+    base: &Cr2wExport,
+    other_file: &Cr2wFile,
+    other: &Cr2wExport,
+    index: usize,
+    changes: &mut Vec<Cr2wChange>,
+) {
+    let export_path = get_export_path(index, base);
+    let other_properties = get_properties(other);
+    let base_properties = get_properties(base);
+
+    for property in &base.properties {
+        let path = format!("{}.{}", export_path, property.name);
+        match other_properties.get(property.name.as_str()) {
+            Some(other_property) => {
+                if !is_same_value(base_file, property, other_file, other_property) {
+                    changes.push(change(
+                        &path,
+                        EChangeKind::Changed,
+                        &base_file.format_value(property),
+                        &other_file.format_value(other_property),
+                    ));
+                }
+            }
+            None => changes.push(change(
+                &path,
+                EChangeKind::Removed,
+                &base_file.format_value(property),
+                "",
+            )),
+        }
+    }
+    for property in &other.properties {
+        if !base_properties.contains_key(property.name.as_str()) {
+            changes.push(change(
+                &format!("{}.{}", export_path, property.name),
+                EChangeKind::Added,
+                "",
+                &other_file.format_value(property),
+            ));
+        }
+    }
+
+    if base.unparsed != other.unparsed {
+        changes.push(change(
+            &format!("{} <native data>", export_path),
+            EChangeKind::Changed,
+            &format_bytes(&base.unparsed),
+            &format_bytes(&other.unparsed),
+        ));
+    }
+}
+
+/// Properties by name, the first one wins if a name is serialized twice
+fn get_properties(export: &Cr2wExport) -> BTreeMap<&str, &Cr2wProperty> {
+    let mut map = BTreeMap::default();
+    for property in &export.properties {
+        map.entry(property.name.as_str()).or_insert(property);
+    }
+    map
+}
+
+/// Values are compared decoded with names resolved, values that can not be decoded byte by byte
+fn is_same_value(a_file: &Cr2wFile, a: &Cr2wProperty, b_file: &Cr2wFile, b: &Cr2wProperty) -> bool {
+    match (a_file.decode_value(a), b_file.decode_value(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.type_name == b.type_name && a.value == b.value,
+    }
+}
+
+fn get_export_path(index: usize, export: &Cr2wExport) -> String {
+    format!("#{} {}", index, export.class_name)
+}
+
+fn change(path: &str, kind: EChangeKind, old: &str, new: &str) -> Cr2wChange {
+    Cr2wChange {
+        path: path.to_owned(),
+        kind,
+        old: old.to_owned(),
+        new: new.to_owned(),
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{get_archive_names, is_redmod};
+
+/// Get the folder disabled archives are moved to, e.g. /archive/pc/mod_disabled
+pub fn get_disabled_path(game_path: &Path) -> Option<PathBuf> {
+    let name = game_path.file_name()?.to_string_lossy();
+    let parent = game_path.parent()?;
+    Some(parent.join(format!("{}_disabled", name)))
+}
+
+/// Get the file names of all disabled archives
+pub fn get_disabled_archives(game_path: &Path) -> Vec<String> {
+    let mut archives = get_disabled_path(game_path)
+        .map(|p| get_archive_names(&p))
+        .unwrap_or_default();
+    archives.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+    archives
+}
+
+/// Move an archive and its .xl file to the disabled folder
+pub fn disable_archive(game_path: &Path, archive_name: &str) -> io::Result<()> {
+    let disabled_path = get_disabled_path(game_path).ok_or(io::ErrorKind::NotFound)?;
+    fs::create_dir_all(&disabled_path)?;
+    move_archive(game_path, &disabled_path, archive_name)
+}
+
+/// Move a disabled archive and its .xl file back to the archive folder
+pub fn enable_archive(game_path: &Path, archive_name: &str) -> io::Result<()> {
+    let disabled_path = get_disabled_path(game_path).ok_or(io::ErrorKind::NotFound)?;
+    move_archive(&disabled_path, game_path, archive_name)
+}
+
+fn move_archive(from: &Path, to: &Path, archive_name: &str) -> io::Result<()> {
+    // REDmods are managed by REDmod
+    if is_redmod(archive_name) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "REDmod archives can't be disabled",
+        ));
+    }
+    if to.join(archive_name).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists in {}", archive_name, to.display()),
+        ));
+    }
+
+    fs::rename(from.join(archive_name), to.join(archive_name))?;
+    let xl_name = format!("{}.xl", archive_name);
+    if from.join(&xl_name).exists() {
+        fs::rename(from.join(&xl_name), to.join(&xl_name))?;
+    }
+    Ok(())
+}
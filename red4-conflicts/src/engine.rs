@@ -0,0 +1,539 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use red4lib::fnv1a64_hash_path;
+
+use crate::{
+    cache::{ArchiveIndex, IndexCache},
+    get_archive_path,
+    install::GameInstall,
+    xl::{get_xl_conflicts, read_xl_files, XlEntry},
+};
+
+/// Conflict info of a single archive
+///
+/// All file hash lists are sorted, use the `is_*` methods to look up single files.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveViewModel {
+    /// name in the load order, REDmod archives are relative to the game root
+    pub file_name: String,
+    /// winning file hashes
+    pub wins: Vec<u64>,
+    /// losing file hashes
+    pub loses: Vec<u64>,
+    /// all file hashes
+    pub files: Vec<u64>,
+    /// file hashes that are neither winning nor losing
+    pub no_conflicts: Vec<u64>,
+    /// number of winning files that are identical duplicates
+    pub identical_wins: usize,
+    /// number of losing files that are identical duplicates
+    pub identical_loses: usize,
+    /// entry list the archive was read from, shared with the index cache
+    pub index: Arc<ArchiveIndex>,
+    /// file hashes that replace base game files, empty if base game archives are not indexed
+    pub overrides: Vec<u64>,
+}
+
+impl ArchiveViewModel {
+    /// file hashes that are neither winning nor losing
+    pub fn get_no_conflicts(&self) -> &[u64] {
+        &self.no_conflicts
+    }
+
+    pub fn is_winning(&self, file_hash: &u64) -> bool {
+        self.wins.binary_search(file_hash).is_ok()
+    }
+
+    pub fn is_losing(&self, file_hash: &u64) -> bool {
+        self.loses.binary_search(file_hash).is_ok()
+    }
+
+    pub fn contains(&self, file_hash: &u64) -> bool {
+        self.files.binary_search(file_hash).is_ok()
+    }
+
+    /// Checks if a file replaces a base game file
+    pub fn is_override(&self, file_hash: &u64) -> bool {
+        self.overrides.binary_search(file_hash).is_ok()
+    }
+
+    /// file hashes that are not in the base game
+    pub fn get_new_files(&self) -> Vec<u64> {
+        self.files
+            .iter()
+            .filter(|h| !self.is_override(h))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of files whose path is not in `hashes`
+    pub fn get_unresolved_count(&self, hashes: &HashMap<u64, String>) -> usize {
+        self.files
+            .iter()
+            .filter(|h| !hashes.contains_key(h))
+            .count()
+    }
+
+    /// Number of winning files, optionally without identical duplicates
+    pub fn get_win_count(&self, hide_identical: bool) -> usize {
+        if hide_identical {
+            self.wins.len() - self.identical_wins
+        } else {
+            self.wins.len()
+        }
+    }
+
+    /// Number of losing files, optionally without identical duplicates
+    pub fn get_lose_count(&self, hide_identical: bool) -> usize {
+        if hide_identical {
+            self.loses.len() - self.identical_loses
+        } else {
+            self.loses.len()
+        }
+    }
+}
+
+/// Result of a conflict check
+#[derive(Debug, Clone, Default)]
+pub struct ConflictReport {
+    /// archives keyed by the hash of their path
+    pub archives: HashMap<u64, ArchiveViewModel>,
+    /// map of file hashes to archive hashes, ordered from lowest to highest priority
+    pub conflicts: HashMap<u64, Vec<u64>>,
+    /// conflicting file hashes where all archives contain byte-identical content
+    pub identical: HashSet<u64>,
+    /// ArchiveXL registrations made by more than one archive, mapped to the archive names
+    pub xl_conflicts: HashMap<XlEntry, Vec<String>>,
+    /// base game archives were indexed and `overrides` of the archives are set
+    pub has_vanilla: bool,
+}
+
+impl ConflictReport {
+    /// Get an archive by the hash of its path
+    pub fn get_archive(&self, archive_hash: &u64) -> Option<&ArchiveViewModel> {
+        self.archives.get(archive_hash)
+    }
+
+    /// Get the archives containing a conflicting file, the last one wins
+    pub fn get_stack(&self, file_hash: &u64) -> Option<&Vec<u64>> {
+        self.conflicts.get(file_hash)
+    }
+
+    /// Get the archive that wins a conflicting file
+    pub fn get_winner(&self, file_hash: &u64) -> Option<u64> {
+        self.get_stack(file_hash).and_then(|s| s.last().cloned())
+    }
+
+    /// Checks if all archives of a conflicting file contain the same content
+    pub fn is_identical(&self, file_hash: &u64) -> bool {
+        self.identical.contains(file_hash)
+    }
+
+    /// Get the ArchiveXL conflicts of an archive, sorted by entry
+    pub fn get_xl_conflicts(&self, archive_name: &str) -> Vec<(&XlEntry, &Vec<String>)> {
+        let mut result = self
+            .xl_conflicts
+            .iter()
+            .filter(|(_, archives)| archives.iter().any(|a| a == archive_name))
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| a.0.cmp(b.0));
+        result
+    }
+
+    /// Get the ArchiveXL conflicts of .xl files that are not named after an archive of the load order
+    pub fn get_unlinked_xl_conflicts(
+        &self,
+        load_order: &[String],
+    ) -> Vec<(&XlEntry, &Vec<String>)> {
+        let mut result = self
+            .xl_conflicts
+            .iter()
+            .filter(|(_, archives)| archives.iter().any(|a| !load_order.contains(a)))
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| a.0.cmp(b.0));
+        result
+    }
+
+    /// Get the file name of an archive or its hash if unknown
+    pub fn get_archive_name(&self, archive_hash: &u64) -> String {
+        match self.archives.get(archive_hash) {
+            Some(vm) => vm.file_name.to_owned(),
+            None => archive_hash.to_string(),
+        }
+    }
+}
+
+/// Progress of a conflict check, shared with the thread running it
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    /// number of archives to read
+    total: AtomicUsize,
+    /// number of archives read
+    done: AtomicUsize,
+    /// archive that is currently read
+    current: Mutex<String>,
+    cancelled: AtomicBool,
+}
+
+impl ScanProgress {
+    /// Stop the scan as soon as possible
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn get_total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    pub fn get_done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Read progress between 0 and 1
+    pub fn get_fraction(&self) -> f32 {
+        let total = self.get_total();
+        if total == 0 {
+            return 1.0;
+        }
+        self.get_done() as f32 / total as f32
+    }
+
+    pub fn get_current(&self) -> String {
+        self.current.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    pub(crate) fn start(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+        self.done.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_current(&self, current: &str) {
+        if let Ok(mut c) = self.current.lock() {
+            *c = current.to_owned();
+        }
+    }
+}
+
+/// Computes conflicts between archives of a load order
+///
+/// Archives that were read before and did not change are not read again.
+#[derive(Debug, Default)]
+pub struct ConflictEngine {
+    /// archive entry lists keyed by archive path
+    index: IndexCache,
+    /// also read the base game archives to find overridden files
+    index_vanilla: bool,
+}
+
+impl ConflictEngine {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create an engine that persists archive entry lists to a folder between runs,
+    /// the folder is read on the first scan
+    pub fn with_index_dir(dir: &Path) -> Self {
+        Self {
+            index: IndexCache::new(dir),
+            ..Default::default()
+        }
+    }
+
+    /// Read the base game archives as well, archives get their `overrides` set
+    pub fn set_index_vanilla(&mut self, index_vanilla: bool) {
+        self.index_vanilla = index_vanilla;
+    }
+
+    /// Base game archive that contains a file, only known if the base game archives are indexed
+    pub fn find_vanilla_archive(&self, game_path: &Path, file_hash: u64) -> Option<PathBuf> {
+        if !self.index_vanilla {
+            return None;
+        }
+        let install = GameInstall::from_path(game_path)?;
+        install
+            .get_vanilla_archives()
+            .into_iter()
+            .find(|p| self.index.get(p).is_some_and(|i| i.contains(&file_hash)))
+    }
+
+    /// Drop all archives of previous runs
+    pub fn clear_cache(&mut self) {
+        self.index.clear();
+    }
+
+    /// Compute the conflicts of a load order. Archive names are relative to `game_path`
+    /// (see [`get_archive_path`]), the first archive in the load order has the highest priority.
+    pub fn generate_conflict_map(
+        &mut self,
+        game_path: &Path,
+        load_order: &[String],
+    ) -> ConflictReport {
+        self.generate_conflict_map_with_progress(game_path, load_order, &ScanProgress::default())
+            .unwrap_or_default()
+    }
+
+    /// Same as [`ConflictEngine::generate_conflict_map`], but reports progress and can be
+    /// cancelled from another thread. Returns `None` if the scan was cancelled.
+    pub fn generate_conflict_map_with_progress(
+        &mut self,
+        game_path: &Path,
+        load_order: &[String],
+        progress: &ScanProgress,
+    ) -> Option<ConflictReport> {
+        // read changed archives in parallel
+        let archive_paths = load_order
+            .iter()
+            .map(|n| get_archive_path(game_path, n))
+            .collect::<Vec<PathBuf>>();
+        // base game archives, only needed to find overridden files
+        let vanilla_paths = match GameInstall::from_path(game_path) {
+            Some(install) if self.index_vanilla => install.get_vanilla_archives(),
+            _ => vec![],
+        };
+        self.index.update(&archive_paths, &vanilla_paths, progress);
+        if progress.is_cancelled() {
+            self.index.save();
+            return None;
+        }
+        progress.set_current("checking conflicts");
+
+        // get the archives from lowest to highest priority, unreadable archives are skipped
+        let mut archives = vec![];
+        for (archive_name, archive_file_path) in load_order.iter().zip(&archive_paths).rev() {
+            if let Some(index) = self.index.get(archive_file_path) {
+                let vm = ArchiveViewModel {
+                    file_name: archive_name.to_owned(),
+                    files: index.get_files().to_vec(),
+                    index: index.clone(),
+                    ..Default::default()
+                };
+                archives.push((fnv1a64_hash_path(archive_file_path), vm));
+            }
+        }
+
+        let mut report = compute_conflicts(archives);
+
+        if !vanilla_paths.is_empty() {
+            let vanilla = vanilla_paths
+                .iter()
+                .filter_map(|p| self.index.get(p))
+                .flat_map(|i| i.get_files().iter().cloned())
+                .collect::<HashSet<u64>>();
+            // files stay sorted
+            for vm in report.archives.values_mut() {
+                vm.overrides = vm
+                    .files
+                    .iter()
+                    .filter(|h| vanilla.contains(h))
+                    .cloned()
+                    .collect();
+            }
+            report.has_vanilla = true;
+        }
+
+        // ArchiveXL sidecar files
+        report.xl_conflicts = get_xl_conflicts(&read_xl_files(game_path));
+
+        self.index.save();
+
+        Some(report)
+    }
+}
+
+/// Compute the conflicts between archives, ordered from lowest to highest priority.
+/// Archives are keyed by the hash of their path and need their `files` and `index` set.
+pub fn compute_conflicts(archives: Vec<(u64, ArchiveViewModel)>) -> ConflictReport {
+    let mut report = ConflictReport::default();
+
+    let mut vms: Vec<(u64, ArchiveViewModel)> = Vec::with_capacity(archives.len());
+    for (archive_hash, archive_vm) in archives {
+        if !vms.iter().any(|(h, _)| *h == archive_hash) {
+            vms.push((archive_hash, archive_vm));
+        }
+    }
+
+    // all files of all archives, sorted by file hash and then by priority
+    let mut entries: Vec<(u64, usize)> =
+        Vec::with_capacity(vms.iter().map(|(_, vm)| vm.files.len()).sum());
+    for (i, (_, vm)) in vms.iter().enumerate() {
+        entries.extend(vm.files.iter().map(|h| (*h, i)));
+    }
+    entries.sort_unstable();
+
+    // file hashes come in ascending order, so all lists stay sorted
+    for stack in entries.chunk_by(|a, b| a.0 == b.0) {
+        let hash = stack[0].0;
+        if stack.len() == 1 {
+            vms[stack[0].1].1.no_conflicts.push(hash);
+            continue;
+        }
+
+        // compare the file content of all archives
+        let first = vms[stack[0].1].1.index.get_sha1(&hash);
+        let identical = first.is_some()
+            && stack
+                .iter()
+                .all(|(_, i)| vms[*i].1.index.get_sha1(&hash) == first);
+        if identical {
+            report.identical.insert(hash);
+        }
+
+        // every archive wins against the ones before and loses against the ones after it
+        let last = stack.len() - 1;
+        for (position, (_, i)) in stack.iter().enumerate() {
+            let vm = &mut vms[*i].1;
+            if position > 0 {
+                vm.wins.push(hash);
+                vm.identical_wins += identical as usize;
+            }
+            if position < last {
+                vm.loses.push(hash);
+                vm.identical_loses += identical as usize;
+            }
+        }
+
+        report
+            .conflicts
+            .insert(hash, stack.iter().map(|(_, i)| vms[*i].0).collect());
+    }
+
+    report.archives = vms.into_iter().collect();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// archive with files given as (file hash, content)
+    fn archive(name: &str, files: &[(u64, u8)]) -> ArchiveViewModel {
+        let mut files = files.to_vec();
+        files.sort();
+        let index = ArchiveIndex {
+            files: files.iter().map(|(h, _)| *h).collect(),
+            sha1: files.iter().map(|(_, c)| [*c; 20]).collect(),
+            ..Default::default()
+        };
+        ArchiveViewModel {
+            file_name: name.to_owned(),
+            files: index.files.clone(),
+            index: Arc::new(index),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn higher_priority_archive_wins() {
+        let report = compute_conflicts(vec![
+            (1, archive("a.archive", &[(10, 0), (20, 0)])),
+            (2, archive("b.archive", &[(20, 1), (30, 0)])),
+        ]);
+
+        let a = &report.archives[&1];
+        let b = &report.archives[&2];
+        assert_eq!(a.wins, Vec::<u64>::new());
+        assert_eq!(a.loses, vec![20]);
+        assert_eq!(a.no_conflicts, vec![10]);
+        assert_eq!(b.wins, vec![20]);
+        assert_eq!(b.loses, Vec::<u64>::new());
+        assert_eq!(b.no_conflicts, vec![30]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(report.identical.is_empty());
+    }
+
+    #[test]
+    fn no_conflicts() {
+        let report = compute_conflicts(vec![
+            (1, archive("a.archive", &[(10, 0)])),
+            (2, archive("b.archive", &[(20, 0)])),
+        ]);
+
+        assert!(report.conflicts.is_empty());
+        for vm in report.archives.values() {
+            assert!(vm.wins.is_empty());
+            assert!(vm.loses.is_empty());
+            assert_eq!(vm.no_conflicts, vm.files);
+        }
+    }
+
+    #[test]
+    fn identical_files() {
+        let report = compute_conflicts(vec![
+            (1, archive("a.archive", &[(10, 5), (20, 1)])),
+            (2, archive("b.archive", &[(10, 5), (20, 2)])),
+        ]);
+
+        assert_eq!(report.identical, HashSet::from([10]));
+        assert_eq!(report.archives[&1].identical_loses, 1);
+        assert_eq!(report.archives[&2].identical_wins, 1);
+        assert_eq!(report.archives[&2].wins, vec![10, 20]);
+    }
+
+    #[test]
+    fn conflict_stacks() {
+        // lowest to highest priority, the duplicate of archive 1 is dropped
+        let report = compute_conflicts(vec![
+            (
+                1,
+                archive("low.archive", &[(10, 1), (20, 7), (30, 1), (40, 1)]),
+            ),
+            (2, archive("mid.archive", &[(10, 2), (20, 7), (50, 1)])),
+            (3, archive("high.archive", &[(10, 3), (20, 7), (30, 2)])),
+            (1, archive("low.archive", &[(60, 1)])),
+        ]);
+
+        assert_eq!(report.archives.len(), 3);
+        assert_eq!(report.conflicts[&10], vec![1, 2, 3]);
+        assert_eq!(report.conflicts[&20], vec![1, 2, 3]);
+        assert_eq!(report.conflicts[&30], vec![1, 3]);
+        assert_eq!(report.conflicts.len(), 3);
+        assert_eq!(report.identical, HashSet::from([20]));
+
+        let low = &report.archives[&1];
+        assert_eq!(low.wins, Vec::<u64>::new());
+        assert_eq!(low.loses, vec![10, 20, 30]);
+        assert_eq!(low.no_conflicts, vec![40]);
+        assert_eq!((low.identical_wins, low.identical_loses), (0, 1));
+
+        // the middle archive wins against the lower and loses against the higher one
+        let mid = &report.archives[&2];
+        assert_eq!(mid.wins, vec![10, 20]);
+        assert_eq!(mid.loses, vec![10, 20]);
+        assert_eq!(mid.no_conflicts, vec![50]);
+        assert_eq!((mid.identical_wins, mid.identical_loses), (1, 1));
+
+        let high = &report.archives[&3];
+        assert_eq!(high.wins, vec![10, 20, 30]);
+        assert_eq!(high.loses, Vec::<u64>::new());
+        assert_eq!(high.no_conflicts, Vec::<u64>::new());
+        assert_eq!((high.identical_wins, high.identical_loses), (1, 0));
+    }
+
+    #[test]
+    fn missing_hash_is_not_identical() {
+        let mut high = archive("high.archive", &[(10, 1)]);
+        high.index = Arc::new(ArchiveIndex {
+            files: high.files.clone(),
+            ..Default::default()
+        });
+        let report = compute_conflicts(vec![(1, archive("low.archive", &[(10, 1)])), (2, high)]);
+
+        assert!(report.identical.is_empty());
+        assert_eq!(report.archives[&2].identical_wins, 0);
+    }
+}
@@ -0,0 +1,308 @@
+use std::{fmt, fs, io, path::Path};
+
+use crate::cli::ScanReport;
+
+/// File format of an exported report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EExportFormat {
+    Json,
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl fmt::Display for EExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EExportFormat::Json => write!(f, "JSON"),
+            EExportFormat::Csv => write!(f, "CSV"),
+            EExportFormat::Markdown => write!(f, "Markdown"),
+            EExportFormat::Html => write!(f, "HTML"),
+        }
+    }
+}
+
+impl EExportFormat {
+    pub const ALL: [EExportFormat; 4] = [
+        EExportFormat::Json,
+        EExportFormat::Csv,
+        EExportFormat::Markdown,
+        EExportFormat::Html,
+    ];
+
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            EExportFormat::Json => "json",
+            EExportFormat::Csv => "csv",
+            EExportFormat::Markdown => "md",
+            EExportFormat::Html => "html",
+        }
+    }
+
+    /// Get the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "htm" => Some(EExportFormat::Html),
+            "markdown" => Some(EExportFormat::Markdown),
+            _ => Self::ALL
+                .into_iter()
+                .find(|f| f.get_extension() == extension),
+        }
+    }
+}
+
+/// Write a report to a file
+pub fn export_report(report: &ScanReport, format: EExportFormat, path: &Path) -> io::Result<()> {
+    let text = match format {
+        EExportFormat::Json => serde_json::to_string_pretty(report)?,
+        EExportFormat::Csv => to_csv(report),
+        EExportFormat::Markdown => to_markdown(report),
+        EExportFormat::Html => to_html(report),
+    };
+    fs::write(path, text)
+}
+
+/// A single table: a row for each archive in load order with its counts,
+/// then a row for each archive of each file and ArchiveXL conflict.
+/// Priority is the position in the load order, 1 is the highest.
+pub fn to_csv(report: &ScanReport) -> String {
+    let priority = |archive: &str| {
+        report
+            .load_order
+            .iter()
+            .position(|a| a == archive)
+            .map(|i| (i + 1).to_string())
+            .unwrap_or_default()
+    };
+
+    let mut csv = String::new();
+    csv += "kind,entry,hash,identical,archive,priority,winner,wins,loses,unique,vanilla_overrides,unresolved,files\n";
+    for (i, archive) in report.archives.iter().enumerate() {
+        csv += &format!(
+            "archive,,,,{},{},,{},{},{},{},{},{}\n",
+            escape_csv(&archive.file_name),
+            i + 1,
+            archive.wins,
+            archive.loses,
+            archive.unique,
+            archive.vanilla_overrides,
+            archive.unresolved,
+            archive.files
+        );
+    }
+    for conflict in &report.conflicts {
+        // lowest to highest priority
+        let last = conflict.archives.len().saturating_sub(1);
+        for (i, archive) in conflict.archives.iter().enumerate() {
+            csv += &format!(
+                "file,{},{},{},{},{},{},,,,,,\n",
+                escape_csv(&conflict.name),
+                conflict.hash,
+                conflict.identical,
+                escape_csv(archive),
+                priority(archive),
+                i == last
+            );
+        }
+    }
+    // ArchiveXL registrations have no winner
+    for conflict in &report.xl_conflicts {
+        for archive in &conflict.archives {
+            csv += &format!(
+                "{},{},,,{},{},,,,,,,\n",
+                escape_csv(&format!("xl {}", conflict.entry.kind)),
+                escape_csv(&conflict.entry.key),
+                escape_csv(archive),
+                priority(archive)
+            );
+        }
+    }
+    csv
+}
+
+pub fn to_markdown(report: &ScanReport) -> String {
+    let mut md = String::new();
+    md += "# Conflict report\n\n";
+    md += &format!(
+        "`{}`: {} conflicts and {} ArchiveXL conflicts across {} archives\n\n",
+        report.game_path.display(),
+        report.conflicts.len(),
+        report.xl_conflicts.len(),
+        report.load_order.len()
+    );
+
+    md += "## Load order\n\nHighest priority first.\n\n";
+    md += "| # | Archive | Wins | Loses | Unique | Vanilla overrides | Unresolved | Files |\n";
+    md += "|---|---|---|---|---|---|---|---|\n";
+    for (i, archive) in report.archives.iter().enumerate() {
+        md += &format!(
+            "| {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            i + 1,
+            escape_markdown(&archive.file_name),
+            archive.wins,
+            archive.loses,
+            archive.unique,
+            archive.vanilla_overrides,
+            archive.unresolved,
+            archive.files
+        );
+    }
+
+    md += "\n## Conflicts\n\nArchives from lowest to highest priority, the last one wins.\n\n";
+    md += "| File | Winner | Archives | Identical |\n";
+    md += "|---|---|---|---|\n";
+    for conflict in &report.conflicts {
+        md += &format!(
+            "| {} | {} | {} | {} |\n",
+            escape_markdown(&conflict.name),
+            escape_markdown(
+                conflict
+                    .archives
+                    .last()
+                    .map(|a| a.as_str())
+                    .unwrap_or_default()
+            ),
+            escape_markdown(&conflict.archives.join(" > ")),
+            if conflict.identical { "yes" } else { "" }
+        );
+    }
+
+    if !report.xl_conflicts.is_empty() {
+        md += "\n## ArchiveXL conflicts\n\n";
+        md += "| Kind | Entry | Archives |\n";
+        md += "|---|---|---|\n";
+        for conflict in &report.xl_conflicts {
+            md += &format!(
+                "| {} | {} | {} |\n",
+                conflict.entry.kind,
+                escape_markdown(&conflict.entry.key),
+                escape_markdown(&conflict.archives.join(", "))
+            );
+        }
+    }
+    md
+}
+
+/// A single html page with inline styles and a filter box
+pub fn to_html(report: &ScanReport) -> String {
+    let mut html = String::new();
+    html += r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Conflict report</title>
+<style>
+body { font-family: sans-serif; margin: 2em; background: #1b1b1b; color: #ddd; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #444; padding: 2px 8px; text-align: left; }
+th { background: #2b2b2b; }
+.win { color: #6c6; }
+.lose { color: #e66; }
+.identical { color: #888; }
+</style>
+<script>
+function filterRows(text) {
+  text = text.toLowerCase();
+  for (const row of document.querySelectorAll("tbody tr")) {
+    row.style.display = row.textContent.toLowerCase().includes(text) ? "" : "none";
+  }
+}
+</script>
+</head>
+<body>
+<h1>Conflict report</h1>
+"#;
+    html += &format!(
+        "<p><code>{}</code>: {} conflicts and {} ArchiveXL conflicts across {} archives</p>\n",
+        escape_html(&report.game_path.display().to_string()),
+        report.conflicts.len(),
+        report.xl_conflicts.len(),
+        report.load_order.len()
+    );
+    html += "<p><input type=\"search\" placeholder=\"Filter\" oninput=\"filterRows(this.value)\"></p>\n";
+
+    html += "<h2>Load order</h2>\n<p>Highest priority first.</p>\n<table>\n";
+    html += "<thead><tr><th>#</th><th>Archive</th><th>Wins</th><th>Loses</th><th>Unique</th><th>Vanilla overrides</th><th>Unresolved</th><th>Files</th></tr></thead>\n<tbody>\n";
+    for (i, archive) in report.archives.iter().enumerate() {
+        html += &format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"win\">{}</td><td class=\"lose\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            i + 1,
+            escape_html(&archive.file_name),
+            archive.wins,
+            archive.loses,
+            archive.unique,
+            archive.vanilla_overrides,
+            archive.unresolved,
+            archive.files
+        );
+    }
+    html += "</tbody>\n</table>\n";
+
+    html += "<h2>Conflicts</h2>\n<p>Archives from lowest to highest priority, the last one wins.</p>\n<table>\n";
+    html += "<thead><tr><th>File</th><th>Archives</th></tr></thead>\n<tbody>\n";
+    for conflict in &report.conflicts {
+        let last = conflict.archives.len().saturating_sub(1);
+        let archives = conflict
+            .archives
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let class = if i == last { "win" } else { "lose" };
+                format!("<span class=\"{}\">{}</span>", class, escape_html(a))
+            })
+            .collect::<Vec<_>>()
+            .join(" &gt; ");
+        let class = if conflict.identical {
+            " class=\"identical\" title=\"identical duplicate\""
+        } else {
+            ""
+        };
+        html += &format!(
+            "<tr{}><td>{}</td><td>{}</td></tr>\n",
+            class,
+            escape_html(&conflict.name),
+            archives
+        );
+    }
+    html += "</tbody>\n</table>\n";
+
+    if !report.xl_conflicts.is_empty() {
+        html += "<h2>ArchiveXL conflicts</h2>\n<table>\n";
+        html += "<thead><tr><th>Kind</th><th>Entry</th><th>Archives</th></tr></thead>\n<tbody>\n";
+        for conflict in &report.xl_conflicts {
+            html += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                conflict.entry.kind,
+                escape_html(&conflict.entry.key),
+                escape_html(&conflict.archives.join(", "))
+            );
+        }
+        html += "</tbody>\n</table>\n";
+    }
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+fn escape_csv(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('_', "\\_")
+        .replace('*', "\\*")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
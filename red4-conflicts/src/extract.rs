@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use red4lib::fnv1a64_hash_path;
+
+use crate::{get_archive_path, ConflictReport};
+
+/// Hash of a file given as a depot path or as a hash
+pub fn get_file_hash(file: &str) -> u64 {
+    match file.trim().parse::<u64>() {
+        Ok(hash) => hash,
+        // depot paths use backslashes
+        Err(_) => fnv1a64_hash_path(Path::new(&file.trim().replace('/', "\\"))),
+    }
+}
+
+/// Folder name of an archive, REDmod archives are flattened to a single folder
+pub fn get_version_folder_name(archive_name: &str) -> String {
+    let name = archive_name
+        .strip_suffix(".archive")
+        .unwrap_or(archive_name);
+    name.replace(['/', '\\'], "_")
+}
+
+/// Relative file path of a depot path, folders that would leave the parent folder are dropped
+pub fn get_relative_path(depot_path: &str) -> PathBuf {
+    depot_path
+        .split(['\\', '/'])
+        .filter(|p| !p.is_empty() && *p != "." && *p != "..")
+        .collect()
+}
+
+/// The content of a file in one archive
+#[derive(Debug, Clone)]
+pub struct FileVersion {
+    pub archive_name: String,
+    pub data: Vec<u8>,
+}
+
+/// Archives that contain a file from lowest to highest priority
+pub fn get_file_archives(report: &ConflictReport, file_hash: u64) -> Vec<u64> {
+    // files that are not in conflict are in a single archive
+    match report.get_stack(&file_hash) {
+        Some(stack) => stack.clone(),
+        None => report
+            .archives
+            .iter()
+            .filter(|(_, vm)| vm.contains(&file_hash))
+            .map(|(archive_hash, _)| *archive_hash)
+            .collect(),
+    }
+}
+
+/// Read the version of a file from every archive that contains it, from lowest to highest priority
+pub fn read_versions(
+    game_path: &Path,
+    report: &ConflictReport,
+    file_hash: u64,
+) -> io::Result<Vec<FileVersion>> {
+    let stack = get_file_archives(report, file_hash);
+    if stack.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("File {} is not in any archive", file_hash),
+        ));
+    }
+
+    let mut versions = vec![];
+    for archive_hash in stack {
+        let archive_name = report.get_archive_name(&archive_hash);
+        let archive_path = get_archive_path(game_path, &archive_name);
+        match read_entry(&archive_path, file_hash)? {
+            Some(data) => versions.push(FileVersion { archive_name, data }),
+            None => log::warn!("{} is not in {}", file_hash, archive_path.display()),
+        }
+    }
+    Ok(versions)
+}
+
+/// Read the base game version of a file from the base game archive that contains it,
+/// see [`crate::ConflictEngine::find_vanilla_archive`]
+pub fn read_vanilla_version(
+    archive_path: &Path,
+    file_hash: u64,
+) -> io::Result<Option<FileVersion>> {
+    let archive_name = archive_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(read_entry(archive_path, file_hash)?.map(|data| FileVersion { archive_name, data }))
+}
+
+/// Read a file of an archive, `None` if the archive does not contain it
+pub fn read_entry(archive_path: &Path, file_hash: u64) -> io::Result<Option<Vec<u8>>> {
+    let mut archive = red4lib::archive::open_read(archive_path)?;
+    let Some(entry) = archive.get_entry(&file_hash).cloned() else {
+        return Ok(None);
+    };
+    let mut data = vec![];
+    archive.extract_entry(entry, &mut data, true)?;
+    Ok(Some(data))
+}
+
+/// Write the version of a file from every archive that contains it to `<out_dir>/<archive>/<depot path>`.
+/// Paths are resolved with `hashes`, returns the written files from lowest to highest priority.
+pub fn extract_versions(
+    game_path: &Path,
+    report: &ConflictReport,
+    file_hash: u64,
+    hashes: &HashMap<u64, String>,
+    out_dir: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    let depot_path = hashes
+        .get(&file_hash)
+        .cloned()
+        .unwrap_or_else(|| file_hash.to_string());
+    let relative_path = get_relative_path(&depot_path);
+
+    let mut written = vec![];
+    for version in read_versions(game_path, report, file_hash)? {
+        let path = out_dir
+            .join(get_version_folder_name(&version.archive_name))
+            .join(&relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &version.data)?;
+        written.push(path);
+    }
+    Ok(written)
+}
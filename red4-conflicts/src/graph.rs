@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ConflictReport;
+
+/// Files that two archives both contain
+#[derive(Debug, Clone)]
+pub struct ArchivePair {
+    /// archive hash of the archive with the higher priority
+    pub winner: u64,
+    /// archive hash of the archive with the lower priority
+    pub loser: u64,
+    /// sorted hashes of the shared files
+    pub files: Vec<u64>,
+    /// number of shared files that are identical duplicates
+    pub identical: usize,
+}
+
+impl ArchivePair {
+    /// Number of shared files, optionally without identical duplicates
+    pub fn get_count(&self, hide_identical: bool) -> usize {
+        if hide_identical {
+            self.files.len() - self.identical
+        } else {
+            self.files.len()
+        }
+    }
+}
+
+/// Get all pairs of archives that share files, most shared files first
+pub fn get_archive_pairs(report: &ConflictReport) -> Vec<ArchivePair> {
+    let mut map: HashMap<(u64, u64), ArchivePair> = HashMap::default();
+    for (file_hash, stack) in &report.conflicts {
+        let identical = report.is_identical(file_hash);
+        // stacks are ordered from lowest to highest priority
+        for (i, loser) in stack.iter().enumerate() {
+            for winner in &stack[i + 1..] {
+                let pair = map.entry((*winner, *loser)).or_insert_with(|| ArchivePair {
+                    winner: *winner,
+                    loser: *loser,
+                    files: vec![],
+                    identical: 0,
+                });
+                pair.files.push(*file_hash);
+                pair.identical += identical as usize;
+            }
+        }
+    }
+
+    let mut pairs = map.into_values().collect::<Vec<_>>();
+    for pair in &mut pairs {
+        pair.files.sort();
+    }
+    pairs.sort_by(|a, b| {
+        b.files
+            .len()
+            .cmp(&a.files.len())
+            .then((a.winner, a.loser).cmp(&(b.winner, b.loser)))
+    });
+    pairs
+}
+
+/// Archive pairs of a report arranged for the conflict matrix
+#[derive(Debug, Clone, Default)]
+pub struct ConflictMatrix {
+    /// see [`get_archive_pairs`]
+    pub pairs: Vec<ArchivePair>,
+    /// index in `pairs` keyed by winner and loser, without pairs that have no shown files
+    pub lookup: HashMap<(u64, u64), usize>,
+    /// archive hashes with shown conflicts in load order
+    pub archives: Vec<u64>,
+    /// identical duplicates are not counted
+    pub hide_identical: bool,
+}
+
+impl ConflictMatrix {
+    /// Arrange the pairs of a report, `load_order` are the archive hashes in load order
+    pub fn new(report: &ConflictReport, load_order: &[u64], hide_identical: bool) -> Self {
+        let pairs = get_archive_pairs(report);
+        let lookup = pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.get_count(hide_identical) > 0)
+            .map(|(i, p)| ((p.winner, p.loser), i))
+            .collect::<HashMap<_, _>>();
+        let in_pairs = lookup
+            .keys()
+            .flat_map(|(w, l)| [*w, *l])
+            .collect::<HashSet<_>>();
+        let archives = load_order
+            .iter()
+            .filter(|h| in_pairs.contains(h))
+            .cloned()
+            .collect();
+
+        Self {
+            pairs,
+            lookup,
+            archives,
+            hide_identical,
+        }
+    }
+
+    /// Get the pair of a winning and a losing archive if it has shown files
+    pub fn get(&self, winner: u64, loser: u64) -> Option<&ArchivePair> {
+        self.lookup.get(&(winner, loser)).map(|i| &self.pairs[*i])
+    }
+}
+
+/// Graphviz graph with an edge from the winning to the losing archive of each pair
+pub fn to_dot(report: &ConflictReport, pairs: &[ArchivePair], hide_identical: bool) -> String {
+    let mut dot = String::new();
+    dot += "digraph conflicts {\n";
+    dot += "    rankdir=LR;\n";
+    dot += "    node [shape=box];\n";
+    for pair in pairs {
+        let count = pair.get_count(hide_identical);
+        if count == 0 {
+            continue;
+        }
+        // pairs that only share identical duplicates are harmless
+        let style = if pair.identical == pair.files.len() {
+            ", style=dashed"
+        } else {
+            ""
+        };
+        dot += &format!(
+            "    \"{}\" -> \"{}\" [label=\"{}\", penwidth={:.1}{}];\n",
+            escape_dot(&report.get_archive_name(&pair.winner)),
+            escape_dot(&report.get_archive_name(&pair.loser)),
+            count,
+            1.0 + (count as f32).log10(),
+            style
+        );
+    }
+    dot += "}\n";
+    dot
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
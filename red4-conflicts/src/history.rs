@@ -0,0 +1,50 @@
+/// number of load order states that can be undone
+const MAX_HISTORY: usize = 100;
+
+/// Undo and redo stacks of load order edits
+#[derive(Debug, Default)]
+pub struct LoadOrderHistory {
+    undo: Vec<Vec<String>>,
+    redo: Vec<Vec<String>>,
+}
+
+impl LoadOrderHistory {
+    /// Remember the load order before an edit, clears the redo stack
+    pub fn record(&mut self, previous: Vec<String>) {
+        if self.undo.last() == Some(&previous) {
+            return;
+        }
+        self.undo.push(previous);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Get the load order before the last edit
+    pub fn undo(&mut self, current: Vec<String>) -> Option<Vec<String>> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Get the load order of the last undone edit
+    pub fn redo(&mut self, current: Vec<String>) -> Option<Vec<String>> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,222 @@
+use std::{collections::HashMap, io, path::Path};
+
+/// Metadata of a single file in an archive
+#[derive(Debug, Clone)]
+pub struct EntryInfo {
+    pub hash: u64,
+    /// resolved depot path or the hash if unknown
+    pub path: String,
+    pub sha1: [u8; 20],
+    /// uncompressed size of all segments in bytes
+    pub size: u64,
+    /// compressed size of all segments in bytes
+    pub compressed_size: u64,
+    pub segments: usize,
+}
+
+impl EntryInfo {
+    /// lowercase file extension, empty if there is none
+    pub fn get_extension(&self) -> String {
+        let name = self.path.rsplit(['\\', '/']).next().unwrap_or_default();
+        name.rsplit_once('.')
+            .map(|(_, e)| e.to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+/// Column to sort the entries of an archive by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EEntrySortColumn {
+    #[default]
+    Path,
+    Hash,
+    Sha1,
+    Size,
+    CompressedSize,
+    Segments,
+}
+
+/// Number and size of all files with the same extension
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionTotal {
+    pub extension: String,
+    pub count: usize,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+/// Entries of an archive with sort and search state
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveInspector {
+    pub archive_name: String,
+    pub entries: Vec<EntryInfo>,
+    pub sort_column: EEntrySortColumn,
+    pub ascending: bool,
+    /// case-insensitive search in path, hash and SHA1
+    search: String,
+    /// indices of the entries matching the search, in sort order
+    filtered: Vec<usize>,
+    /// totals per file extension, largest first
+    totals: Vec<ExtensionTotal>,
+}
+
+impl ArchiveInspector {
+    /// Read the entries of an archive, paths are resolved with `hashes`
+    pub fn open(
+        archive_path: &Path,
+        archive_name: &str,
+        hashes: &HashMap<u64, String>,
+    ) -> io::Result<Self> {
+        let mut inspector = Self {
+            archive_name: archive_name.to_owned(),
+            entries: read_entries(archive_path, hashes)?,
+            ascending: true,
+            ..Default::default()
+        };
+        inspector.totals = get_extension_totals(&inspector.entries);
+        inspector.sort();
+        Ok(inspector)
+    }
+
+    /// Sort by a column, sorting by the same column again reverses the order
+    pub fn sort_by(&mut self, column: EEntrySortColumn) {
+        if self.sort_column == column {
+            self.ascending = !self.ascending;
+        } else {
+            self.sort_column = column;
+            self.ascending = true;
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        match self.sort_column {
+            EEntrySortColumn::Path => self.entries.sort_by(|a, b| a.path.cmp(&b.path)),
+            EEntrySortColumn::Hash => self.entries.sort_by_key(|e| e.hash),
+            EEntrySortColumn::Sha1 => self.entries.sort_by_key(|e| e.sha1),
+            EEntrySortColumn::Size => self.entries.sort_by_key(|e| e.size),
+            EEntrySortColumn::CompressedSize => self.entries.sort_by_key(|e| e.compressed_size),
+            EEntrySortColumn::Segments => self.entries.sort_by_key(|e| e.segments),
+        }
+        if !self.ascending {
+            self.entries.reverse();
+        }
+        self.filter();
+    }
+
+    pub fn get_search(&self) -> &str {
+        &self.search
+    }
+
+    /// Set the search text, the entries are only filtered again if it changed
+    pub fn set_search(&mut self, search: &str) {
+        if self.search != search {
+            self.search = search.to_owned();
+            self.filter();
+        }
+    }
+
+    fn filter(&mut self) {
+        let search = self.search.trim().to_lowercase();
+        self.filtered = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| {
+                search.is_empty()
+                    || e.path.to_lowercase().contains(&search)
+                    || e.hash.to_string().contains(&search)
+                    || format_sha1(&e.sha1).contains(&search)
+            })
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    /// Indices of the entries matching the search text, in sort order
+    pub fn get_filtered(&self) -> &[usize] {
+        &self.filtered
+    }
+
+    /// Totals per file extension, largest first
+    pub fn get_extension_totals(&self) -> &[ExtensionTotal] {
+        &self.totals
+    }
+}
+
+/// Totals per file extension, largest first
+fn get_extension_totals(entries: &[EntryInfo]) -> Vec<ExtensionTotal> {
+    let mut map: HashMap<String, ExtensionTotal> = HashMap::default();
+    for entry in entries {
+        let extension = entry.get_extension();
+        let total = map
+            .entry(extension.clone())
+            .or_insert_with(|| ExtensionTotal {
+                extension,
+                ..Default::default()
+            });
+        total.count += 1;
+        total.size += entry.size;
+        total.compressed_size += entry.compressed_size;
+    }
+    let mut totals = map.into_values().collect::<Vec<_>>();
+    totals.sort_by(|a, b| b.size.cmp(&a.size).then(a.extension.cmp(&b.extension)));
+    totals
+}
+
+/// Read the metadata of all files in an archive
+pub fn read_entries(
+    archive_path: &Path,
+    hashes: &HashMap<u64, String>,
+) -> io::Result<Vec<EntryInfo>> {
+    let archive = red4lib::archive::open_read(archive_path)?;
+    let mut entries = vec![];
+    for (hash, zip_entry) in archive.get_entries() {
+        // names stored in the archive take precedence
+        let path = zip_entry
+            .name()
+            .or_else(|| hashes.get(hash))
+            .cloned()
+            .unwrap_or_else(|| hash.to_string());
+
+        // the first segment is the file itself, the others are its buffers
+        let segments = std::iter::once(&zip_entry.segment).chain(zip_entry.buffers.iter());
+        let (size, compressed_size) = segments.fold((0, 0), |(size, z_size), s| {
+            (size + s.size() as u64, z_size + s.z_size() as u64)
+        });
+
+        entries.push(EntryInfo {
+            hash: *hash,
+            path,
+            sha1: zip_entry.entry.sha1_hash(),
+            size,
+            compressed_size,
+            // a corrupt entry must not underflow
+            segments: zip_entry
+                .entry
+                .segments_end()
+                .saturating_sub(zip_entry.entry.segments_start()) as usize,
+        });
+    }
+    Ok(entries)
+}
+
+/// SHA1 as a lowercase hex string
+pub fn format_sha1(sha1: &[u8; 20]) -> String {
+    sha1.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Human readable byte size, e.g. `1.5 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{get_files, get_game_root};
+
+/// Steam app id of Cyberpunk 2077
+const STEAM_APP_ID: &str = "1091500";
+const GAME_FOLDER_NAME: &str = "Cyberpunk 2077";
+
+/// Folders of a Cyberpunk 2077 install
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameInstall {
+    pub root: PathBuf,
+}
+
+impl GameInstall {
+    /// Get the install from its root or from its /archive/pc/mod folder
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if is_install_root(path) {
+            return Some(Self {
+                root: path.to_path_buf(),
+            });
+        }
+        get_game_root(path).map(|root| Self { root })
+    }
+
+    /// /archive/pc/mod
+    pub fn get_mod_path(&self) -> PathBuf {
+        self.root.join("archive").join("pc").join("mod")
+    }
+
+    /// /archive/pc/content
+    pub fn get_content_path(&self) -> PathBuf {
+        self.root.join("archive").join("pc").join("content")
+    }
+
+    /// /archive/pc/ep1
+    pub fn get_ep1_path(&self) -> PathBuf {
+        self.root.join("archive").join("pc").join("ep1")
+    }
+
+    /// /mods
+    pub fn get_redmod_path(&self) -> PathBuf {
+        self.root.join("mods")
+    }
+
+    /// Base game archives in /archive/pc/content and /archive/pc/ep1
+    pub fn get_vanilla_archives(&self) -> Vec<PathBuf> {
+        let mut archives = get_files(&self.get_content_path(), "archive");
+        archives.extend(get_files(&self.get_ep1_path(), "archive"));
+        archives.sort();
+        archives
+    }
+
+    /// Check the layout of the install, returns a list of warnings
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        if !self
+            .root
+            .join("bin")
+            .join("x64")
+            .join("Cyberpunk2077.exe")
+            .exists()
+        {
+            warnings.push(format!(
+                "{} does not contain bin/x64/Cyberpunk2077.exe",
+                self.root.display()
+            ));
+        }
+        if !self.get_content_path().exists() {
+            warnings.push(format!(
+                "{} does not contain archive/pc/content",
+                self.root.display()
+            ));
+        }
+        if !self.get_mod_path().exists() {
+            warnings.push(format!(
+                "{} does not contain archive/pc/mod",
+                self.root.display()
+            ));
+        }
+        warnings
+    }
+}
+
+/// Checks if a folder looks like a Cyberpunk 2077 install root
+pub fn is_install_root(path: &Path) -> bool {
+    path.join("bin")
+        .join("x64")
+        .join("Cyberpunk2077.exe")
+        .exists()
+        || path.join("archive").join("pc").join("content").exists()
+}
+
+/// Get the archive folder to check for a user selected folder.
+/// Install roots resolve to their /archive/pc/mod folder, other folders are used as they are.
+pub fn resolve_archive_folder(path: &Path) -> PathBuf {
+    if is_install_root(path) {
+        return GameInstall {
+            root: path.to_path_buf(),
+        }
+        .get_mod_path();
+    }
+    path.to_path_buf()
+}
+
+/// Check a selected archive folder, returns a list of warnings
+pub fn validate_archive_folder(game_path: &Path) -> Vec<String> {
+    if !game_path.exists() {
+        return vec![format!("{} does not exist", game_path.display())];
+    }
+    match GameInstall::from_path(game_path) {
+        Some(install) => install.validate(),
+        None => vec![format!(
+            "{} is not inside a Cyberpunk 2077 install, REDmods are not checked",
+            game_path.display()
+        )],
+    }
+}
+
+/// Search Steam, GOG and Heroic install locations for Cyberpunk 2077 installs
+pub fn find_installs() -> Vec<PathBuf> {
+    let mut candidates = vec![];
+
+    // Steam libraries
+    for steam_root in get_steam_roots() {
+        let vdf = steam_root.join("steamapps").join("libraryfolders.vdf");
+        let mut libraries = vec![steam_root.clone()];
+        if let Ok(text) = fs::read_to_string(&vdf) {
+            libraries.extend(parse_library_folders(&text));
+        }
+
+        for library in libraries {
+            let steamapps = library.join("steamapps");
+            candidates.push(steamapps.join("common").join(GAME_FOLDER_NAME));
+
+            // games installed inside Proton prefixes
+            if let Ok(entries) = fs::read_dir(steamapps.join("compatdata")) {
+                for entry in entries.flatten() {
+                    let drive_c = entry.path().join("pfx").join("drive_c");
+                    candidates.extend(get_gog_defaults(&drive_c));
+                }
+            }
+        }
+    }
+
+    // GOG default locations
+    if cfg!(windows) {
+        for drive in ["C:", "D:", "E:"] {
+            candidates.extend(get_gog_defaults(&PathBuf::from(format!("{}\\", drive))));
+        }
+    }
+
+    // Heroic (GOG and Epic)
+    for heroic_config in get_heroic_config_paths() {
+        for file in [
+            heroic_config.join("gog_store").join("installed.json"),
+            heroic_config
+                .join("legendaryConfig")
+                .join("legendary")
+                .join("installed.json"),
+        ] {
+            if let Ok(text) = fs::read_to_string(&file) {
+                candidates.extend(parse_heroic_installed(&text));
+            }
+        }
+    }
+
+    let mut installs: Vec<PathBuf> = vec![];
+    for candidate in candidates {
+        if is_install_root(&candidate) && !installs.contains(&candidate) {
+            installs.push(candidate);
+        }
+    }
+    installs
+}
+
+/// Get library paths from a Steam libraryfolders.vdf
+///
+/// ```text
+/// "libraryfolders"
+/// {
+///     "0"
+///     {
+///         "path"      "C:\\Program Files (x86)\\Steam"
+///         "apps"
+///         {
+///             "1091500"   "70000000000"
+/// ```
+pub fn parse_library_folders(text: &str) -> Vec<PathBuf> {
+    let mut libraries = vec![];
+    let mut current_path: Option<PathBuf> = None;
+    let mut has_game = false;
+
+    for line in text.lines() {
+        let tokens = line
+            .split('"')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        match tokens.as_slice() {
+            ["path", path] => {
+                // a new library starts
+                if let Some(path) = current_path.take() {
+                    if has_game {
+                        libraries.insert(0, path);
+                    } else {
+                        libraries.push(path);
+                    }
+                }
+                current_path = Some(PathBuf::from(path.replace("\\\\", "\\")));
+                has_game = false;
+            }
+            [STEAM_APP_ID, _] => has_game = true,
+            _ => {}
+        }
+    }
+    if let Some(path) = current_path {
+        if has_game {
+            libraries.insert(0, path);
+        } else {
+            libraries.push(path);
+        }
+    }
+
+    // libraries that contain the game come first
+    libraries
+}
+
+/// Get install paths from a Heroic installed.json
+fn parse_heroic_installed(text: &str) -> Vec<PathBuf> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return vec![];
+    };
+
+    // gog_store/installed.json has a list, legendary/installed.json a map
+    let games: Vec<&serde_json::Value> = match &json {
+        serde_json::Value::Object(map) => match map.get("installed") {
+            Some(serde_json::Value::Array(list)) => list.iter().collect(),
+            _ => map.values().collect(),
+        },
+        _ => vec![],
+    };
+
+    games
+        .iter()
+        .filter_map(|g| g.get("install_path").and_then(|p| p.as_str()))
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn get_gog_defaults(drive: &Path) -> Vec<PathBuf> {
+    vec![
+        drive.join("GOG Games").join(GAME_FOLDER_NAME),
+        drive
+            .join("Program Files (x86)")
+            .join("GOG Galaxy")
+            .join("Games")
+            .join(GAME_FOLDER_NAME),
+        drive
+            .join("Program Files")
+            .join("GOG Galaxy")
+            .join("Games")
+            .join(GAME_FOLDER_NAME),
+    ]
+}
+
+fn get_steam_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+    if cfg!(windows) {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Ok(program_files) = env::var(var) {
+                roots.push(PathBuf::from(program_files).join("Steam"));
+            }
+        }
+    } else if let Ok(home) = env::var("HOME") {
+        let home = PathBuf::from(home);
+        roots.push(home.join(".steam").join("steam"));
+        roots.push(home.join(".local").join("share").join("Steam"));
+        roots.push(
+            home.join(".var")
+                .join("app")
+                .join("com.valvesoftware.Steam")
+                .join(".local")
+                .join("share")
+                .join("Steam"),
+        );
+    }
+    roots
+}
+
+fn get_heroic_config_paths() -> Vec<PathBuf> {
+    let mut paths = vec![];
+    if cfg!(windows) {
+        if let Ok(appdata) = env::var("APPDATA") {
+            paths.push(PathBuf::from(appdata).join("heroic"));
+        }
+    } else if let Ok(home) = env::var("HOME") {
+        let home = PathBuf::from(home);
+        paths.push(home.join(".config").join("heroic"));
+        paths.push(
+            home.join(".var")
+                .join("app")
+                .join("com.heroicgameslauncher.hgl")
+                .join("config")
+                .join("heroic"),
+        );
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_with_the_game_comes_first() {
+        let text = r#""libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"apps"
+		{
+			"228980"		"300000000"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"apps"
+		{
+			"1091500"		"70000000000"
+		}
+	}
+	"2"
+	{
+		"path"		"E:\\Games"
+	}
+}
+"#;
+        assert_eq!(
+            parse_library_folders(text),
+            vec![
+                PathBuf::from("D:\\SteamLibrary"),
+                PathBuf::from("C:\\Program Files (x86)\\Steam"),
+                PathBuf::from("E:\\Games"),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_libraries() {
+        assert!(parse_library_folders("").is_empty());
+    }
+}
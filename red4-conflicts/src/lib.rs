@@ -1,41 +1,80 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
-use log::error;
-use red4lib::fnv1a64_hash_path;
 use std::fs::{self, File};
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
-use std::{collections::HashMap, path::PathBuf};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
-mod app;
+#[cfg(feature = "gui")]
+use log::error;
+#[cfg(feature = "gui")]
+use std::{collections::HashMap, io::Write, sync::Arc, thread};
 
+#[cfg(feature = "gui")]
+mod app;
+pub mod backup;
+pub mod cache;
+pub mod cli;
+pub mod cr2w;
+pub mod diff;
+pub mod disable;
+pub mod engine;
+pub mod export;
+pub mod extract;
+pub mod graph;
+pub mod history;
+pub mod inspector;
+pub mod install;
+pub mod modlist;
+pub mod patch;
+pub mod pins;
+pub mod profile;
+pub mod rules;
+#[cfg(test)]
+mod test_utils;
+pub mod tree;
+pub mod user_hashes;
+#[cfg(feature = "gui")]
+mod watcher;
+pub mod xl;
+
+#[cfg(feature = "gui")]
+use backup::{backup_modlist, ModlistBackup};
+#[cfg(feature = "gui")]
+use diff::{compare_versions, Cr2wComparison};
+#[cfg(feature = "gui")]
+use disable::{disable_archive, enable_archive, get_disabled_archives};
+pub use engine::{ArchiveViewModel, ConflictEngine, ConflictReport, ScanProgress};
+#[cfg(feature = "gui")]
+use extract::{read_vanilla_version, read_versions};
+#[cfg(feature = "gui")]
+use graph::ConflictMatrix;
+#[cfg(feature = "gui")]
+use history::LoadOrderHistory;
+#[cfg(feature = "gui")]
+use inspector::ArchiveInspector;
+#[cfg(feature = "gui")]
+use modlist::{EModlistIssue, Modlist};
+#[cfg(feature = "gui")]
+use patch::{build_patch, PatchManifest, PATCH_ARCHIVE_NAME};
+#[cfg(feature = "gui")]
+use pins::{check_pins, solve_pins, EPinSolution, EPinStatus, WinnerPin};
+#[cfg(feature = "gui")]
+use profile::{get_unique_profile_name, ELoadOrderDiff, Profiles, DEFAULT_PROFILE};
+#[cfg(feature = "gui")]
+use rules::{sort_load_order, LoadOrderRules, RULES_TEMPLATE};
+#[cfg(feature = "gui")]
+use tree::{FolderNode, TreeFilter};
+#[cfg(feature = "gui")]
+use user_hashes::UserHashStore;
+
+/// name of the app, also used for the app storage folder
+pub const APP_NAME: &str = "Red4 Conflict Checker";
+#[cfg(feature = "gui")]
 const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
+#[cfg(feature = "gui")]
 const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
-#[derive(Clone)]
-struct ArchiveViewModel {
-    pub file_name: String,
-    /// winning file hashes
-    pub wins: Vec<u64>,
-    /// losing file hashes
-    pub loses: Vec<u64>,
-    /// all file hashes
-    pub files: Vec<u64>,
-}
-
-impl ArchiveViewModel {
-    pub fn get_no_conflicts(&self) -> Vec<u64> {
-        let result: Vec<u64> = self
-            .files
-            .iter()
-            .filter(|&x| !self.wins.contains(x))
-            .filter(|&x| !self.loses.contains(x))
-            .cloned()
-            .collect();
-        result
-    }
-}
-
+#[cfg(feature = "gui")]
 #[derive(Default, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
 enum ETooltipVisuals {
     Tooltip,
@@ -44,41 +83,143 @@ enum ETooltipVisuals {
     Collapsing,
 }
 
+/// A conflict check running on a worker thread
+#[cfg(feature = "gui")]
+struct ScanTask {
+    progress: Arc<ScanProgress>,
+    /// returns the engine and the report, or `None` if cancelled
+    handle: thread::JoinHandle<(ConflictEngine, Option<ConflictReport>)>,
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[cfg(feature = "gui")]
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
     game_path: PathBuf,
     // UI
     show_no_conflicts: bool,
+    /// hide conflicts of byte-identical files
+    hide_identical: bool,
+    /// index the base game archives to show overridden files
+    check_vanilla: bool,
     /// the way conflicts are disaplyed in the conflicts view
     tooltips_visuals: ETooltipVisuals,
 
     /// enables load order management via modlist.txt
     enable_modlist: bool,
+    /// named load orders
+    profiles: Profiles,
+    /// profile that load order changes are saved to
+    active_profile: String,
+    /// load order positions of disabled archives, to put them back when enabled
+    disabled_positions: HashMap<String, usize>,
+    /// files that an archive must win
+    pins: Vec<WinnerPin>,
 
     /// hash DB
     #[serde(skip)]
     hashes: HashMap<u64, String>,
-    /// archive name lookup
+    /// hash lists imported by the user
+    #[serde(skip)]
+    user_hashes: UserHashStore,
+    /// number of files with unknown paths per archive
     #[serde(skip)]
-    archives: HashMap<u64, ArchiveViewModel>,
-    /// map of file hashes to archive hashes
+    unresolved: HashMap<u64, usize>,
+    /// conflict computation, keeps archives between runs
     #[serde(skip)]
-    conflicts: HashMap<u64, Vec<u64>>,
+    engine: ConflictEngine,
+    /// archives and conflicts of the last run
+    #[serde(skip)]
+    report: ConflictReport,
     /// archive hash load order
     #[serde(skip)]
     load_order: Vec<String>,
     #[serde(skip)]
     last_load_order: Option<Vec<String>>,
+    /// undo and redo of load order edits
+    #[serde(skip)]
+    history: LoadOrderHistory,
+    /// archives in the disabled folder
+    #[serde(skip)]
+    disabled: Vec<String>,
+    /// problems with the layout of the game folder
+    #[serde(skip)]
+    install_warnings: Vec<String>,
+    /// problems found when sorting by rules
+    #[serde(skip)]
+    sort_warnings: Vec<String>,
+    /// problems in modlist.txt
+    #[serde(skip)]
+    modlist_issues: Vec<EModlistIssue>,
+    /// running conflict check
+    #[serde(skip)]
+    scan: Option<ScanTask>,
+    /// start another conflict check when the running one stopped
+    #[serde(skip)]
+    rescan: bool,
+    /// refreshes the load order when the mod folders change
+    #[serde(skip)]
+    watcher: Option<watcher::ModWatcher>,
 
     // UI filters
     #[serde(skip)]
     text_filter: String,
     #[serde(skip)]
     file_filter: String,
+    /// name for the next duplicated profile
+    #[serde(skip)]
+    new_profile_name: String,
+    /// profiles shown in the compare window
+    #[serde(skip)]
+    compare_profiles: Option<(String, String)>,
+    /// differences of the compared profiles, only computed again if the selection or the profiles change
+    #[serde(skip)]
+    profile_diffs: Option<(String, String, Vec<ELoadOrderDiff>)>,
+    /// modlist.txt backups shown in the restore window
+    #[serde(skip)]
+    backups: Option<Vec<ModlistBackup>>,
+    /// shows the conflict matrix window
+    #[serde(skip)]
+    show_matrix: bool,
+    /// archives sharing files, computed from the report when the matrix is shown
+    #[serde(skip)]
+    conflict_matrix: Option<ConflictMatrix>,
+    /// winner and loser of the pair selected in the matrix
+    #[serde(skip)]
+    selected_pair: Option<(u64, u64)>,
+    /// entries of the archive shown in the inspector window
+    #[serde(skip)]
+    inspector: Option<ArchiveInspector>,
+    /// file name and structural comparison of its versions
+    #[serde(skip)]
+    comparison: Option<(String, Cr2wComparison)>,
+    /// shows the file tree window
+    #[serde(skip)]
+    show_tree: bool,
+    /// choices of the conflict patch archive
+    #[serde(skip)]
+    patch: PatchManifest,
+    /// shows the conflict patch window
+    #[serde(skip)]
+    show_patch: bool,
+    /// state of each pin in the last conflict report
+    #[serde(skip)]
+    pin_status: Vec<EPinStatus>,
+    /// smallest set of pins that can not be satisfied together
+    #[serde(skip)]
+    pin_contradiction: Vec<WinnerPin>,
+    /// shows the winner pins window
+    #[serde(skip)]
+    show_pins: bool,
+    #[serde(skip)]
+    tree_filter: TreeFilter,
+    /// depot tree of the report and the filter it was built with
+    #[serde(skip)]
+    file_tree: Option<(TreeFilter, FolderNode)>,
 }
 
+#[cfg(feature = "gui")]
 impl TemplateApp {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
@@ -87,152 +228,376 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
+        let mut app: TemplateApp = Default::default();
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            app = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
         }
 
-        Default::default()
+        app.engine = create_engine();
+        app.user_hashes = create_user_hash_store();
+        app
     }
 
-    /// Returns the conflict map of this [`TemplateApp`]. Also sets archive and conflict maps
-    fn generate_conflict_map(&mut self) {
-        let old_archives = self.archives.clone();
-        self.archives.clear();
-        self.conflicts.clear();
-
-        let mut conflict_map: HashMap<u64, Vec<u64>> = HashMap::default();
-
-        // scan
-        let mut mods = self.load_order.clone();
-        mods.reverse();
-
-        for archive_name in mods.iter() {
-            let archive_file_path = &self.game_path.join(archive_name);
-            let archive_hash = fnv1a64_hash_path(archive_file_path);
-            log::info!("parsing {}", archive_file_path.display());
-
-            // read or get the archive
-            let mut archive_or_none: Option<ArchiveViewModel> = None;
-            if let Some(archive) = old_archives.get(&archive_hash) {
-                // no need to read the file again
-                let mut empty_vm = archive.clone();
-                // but clean it since we're calculating conflicts anew
-                empty_vm.wins.clear();
-                empty_vm.loses.clear();
-
-                archive_or_none = Some(empty_vm);
-            } else if let Ok(archive) = red4lib::archive::open_read(archive_file_path) {
-                if let Some(archive_file_name) =
-                    archive_file_path.file_name().and_then(|f| f.to_str())
-                {
-                    // conflicts
-                    let mut hashes = archive
-                        .get_entries()
-                        .clone()
-                        .into_keys()
-                        .collect::<Vec<_>>();
-                    hashes.sort();
-
-                    let vm = ArchiveViewModel {
-                        file_name: archive_file_name.to_owned(),
-                        files: hashes.clone(),
-                        wins: vec![],
-                        loses: vec![],
-                    };
-
-                    archive_or_none = Some(vm);
+    /// Load the built-in and the user hash lists
+    fn load_hashes(&mut self) {
+        self.hashes = red4lib::get_red4_hashes();
+        self.hashes.extend(self.user_hashes.get_hashes().clone());
+        self.count_unresolved();
+        self.file_tree = None;
+    }
+
+    /// Import a hash list picked by the user into the user hash store
+    fn import_hashes(&mut self) {
+        let Some(paths) = rfd::FileDialog::new()
+            .add_filter("Hash lists", &["csv", "txt"])
+            .pick_files()
+        else {
+            return;
+        };
+        for path in paths {
+            match self.user_hashes.import(&path) {
+                Ok(count) => log::info!("Imported {} hashes from {}", count, path.display()),
+                Err(e) => error!("Failed to import {}: {}", path.display(), e),
+            }
+        }
+        self.load_hashes();
+    }
+
+    /// Show the entries of an archive in the inspector window
+    fn inspect_archive(&mut self, archive_name: &str) {
+        let archive_path = get_archive_path(&self.game_path, archive_name);
+        match ArchiveInspector::open(&archive_path, archive_name, &self.hashes) {
+            Ok(inspector) => self.inspector = Some(inspector),
+            Err(e) => error!("Failed to read {}: {}", archive_path.display(), e),
+        }
+    }
+
+    /// Compare the CR2W structure of the versions of a file
+    fn compare_versions(&mut self, file_hash: u64) {
+        let name = self
+            .hashes
+            .get(&file_hash)
+            .cloned()
+            .unwrap_or_else(|| file_hash.to_string());
+        let mut versions = match read_versions(&self.game_path, &self.report, file_hash) {
+            Ok(versions) => versions,
+            Err(e) => {
+                error!("Failed to read {}: {}", name, e);
+                return;
+            }
+        };
+
+        // mods change the base game version, without it the version with the lowest priority is the base.
+        // the base game archive is looked up in the index, opening all of them would block the ui
+        let vanilla = self
+            .engine
+            .find_vanilla_archive(&self.game_path, file_hash)
+            .and_then(|path| match read_vanilla_version(&path, file_hash) {
+                Ok(version) => version,
+                Err(e) => {
+                    error!("Failed to read {}: {}", path.display(), e);
+                    None
+                }
+            });
+        let base = match vanilla {
+            Some(base) => base,
+            None if !versions.is_empty() => versions.remove(0),
+            None => return,
+        };
+        match compare_versions(&base, &versions) {
+            Ok(comparison) => self.comparison = Some((name, comparison)),
+            Err(e) => error!("Failed to compare {}: {}", name, e),
+        }
+    }
+
+    /// Take a file from an archive through the conflict patch archive, no matter where the archive is in the load order
+    fn add_patch_choice(&mut self, file_hash: u64, archive_hash: u64) {
+        let Some(path) = self.hashes.get(&file_hash).cloned() else {
+            error!(
+                "The path of {} is unknown, import a hash list to patch it",
+                file_hash
+            );
+            return;
+        };
+        let mut manifest = PatchManifest::load(&self.game_path);
+        manifest.set_choice(
+            file_hash,
+            &path,
+            &self.report.get_archive_name(&archive_hash),
+        );
+        self.build_patch(manifest);
+    }
+
+    /// Take a file out of the conflict patch archive
+    fn remove_patch_choice(&mut self, file_hash: u64) {
+        let mut manifest = PatchManifest::load(&self.game_path);
+        if manifest.remove_choice(file_hash) {
+            self.build_patch(manifest);
+        }
+    }
+
+    /// Pack the conflict patch archive and load it first
+    fn build_patch(&mut self, manifest: PatchManifest) {
+        match build_patch(&self.game_path, &manifest) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    log::warn!("{}", warning);
                 }
             }
+            Err(e) => {
+                error!("Failed to build {}: {}", PATCH_ARCHIVE_NAME, e);
+                return;
+            }
+        }
+        self.patch = manifest;
+
+        // the patch only wins if it is loaded first, a missing patch is dropped from the load order.
+        // the load order is set again in any case to check the new patch for conflicts
+        let mut load_order = self.load_order.clone();
+        load_order.retain(|n| n != PATCH_ARCHIVE_NAME);
+        load_order.insert(0, PATCH_ARCHIVE_NAME.to_owned());
+        if load_order != self.load_order {
+            self.history.record(self.load_order.clone());
+        }
+        self.set_load_order(&load_order);
+    }
 
-            if let Some(mut archive_vm) = archive_or_none {
-                for hash in &archive_vm.files {
-                    if let Some(archive_names) = conflict_map.get_mut(hash) {
-                        // found a conflict
-                        // update vms
-                        // add this file to all previous archive's losing files
-                        for archive in archive_names.iter() {
-                            if !self.archives.get(archive).unwrap().loses.contains(hash) {
-                                self.archives.get_mut(archive).unwrap().loses.push(*hash);
-                            }
-                        }
-                        // add the current archive to the list of conflicting archives last
-                        if !archive_names.contains(&archive_hash) {
-                            archive_names.push(archive_hash);
-                        }
-                        // add this file to this mods winning files
-                        if !archive_vm.wins.contains(hash) {
-                            archive_vm.wins.push(*hash);
-                        }
-                    } else {
-                        // first occurance
-                        conflict_map.insert(*hash, vec![archive_hash]);
-                    }
+    /// Let an archive win a file by moving it in the load order, replaces an earlier pin of the file
+    fn pin_winner(&mut self, file_hash: u64, archive_hash: u64) {
+        self.pins.retain(|p| p.file != file_hash);
+        self.pins.push(WinnerPin {
+            file: file_hash,
+            archive: self.report.get_archive_name(&archive_hash),
+        });
+        self.show_pins = true;
+        self.solve_pins();
+    }
+
+    fn unpin(&mut self, file_hash: u64) {
+        self.pins.retain(|p| p.file != file_hash);
+        self.pin_contradiction.clear();
+        self.check_pins();
+    }
+
+    /// Move archives so that every pinned archive wins its file
+    fn solve_pins(&mut self) {
+        // the solved load order can only be saved to modlist.txt
+        if !self.enable_modlist {
+            error!("Enable load order re-ordering to solve the load order for pins");
+            return;
+        }
+        match solve_pins(&self.load_order, &self.report, &self.pins) {
+            EPinSolution::Solved(load_order) => {
+                self.pin_contradiction.clear();
+                if load_order != self.load_order {
+                    self.history.record(self.load_order.clone());
+                    self.set_load_order(&load_order);
                 }
+            }
+            EPinSolution::Contradiction(pins) => {
+                log::warn!("{} pins contradict each other", pins.len());
+                self.pin_contradiction = pins;
+            }
+        }
+        self.check_pins();
+    }
 
-                self.archives.insert(archive_hash, archive_vm);
+    /// Check the pins against the last conflict report
+    fn check_pins(&mut self) {
+        self.pin_status = check_pins(&self.report, &self.pins);
+        for (pin, status) in self.pins.iter().zip(&self.pin_status) {
+            if let EPinStatus::Violated(winner) = status {
+                log::warn!(
+                    "{} wins {} instead of the pinned {}",
+                    winner,
+                    self.hashes
+                        .get(&pin.file)
+                        .cloned()
+                        .unwrap_or_else(|| pin.file.to_string()),
+                    pin.archive
+                );
             }
         }
+    }
 
-        // clean list
-        let mut conflicts: HashMap<u64, Vec<u64>> = HashMap::default();
-        for (hash, archives) in conflict_map.iter().filter(|p| p.1.len() > 1) {
-            // insert
-            conflicts.insert(*hash, archives.clone());
+    /// Count the files with unknown paths of each archive
+    fn count_unresolved(&mut self) {
+        self.unresolved = self
+            .report
+            .archives
+            .iter()
+            .map(|(k, vm)| (*k, vm.get_unresolved_count(&self.hashes)))
+            .collect();
+    }
+
+    /// Starts computing the conflict map of this [`TemplateApp`] on a worker thread.
+    /// Archive and conflict maps are set in [`TemplateApp::poll_scan`] once it finished.
+    fn generate_conflict_map(&mut self) {
+        // changes on disk up to here are part of this check
+        if let Some(watcher) = self.watcher.as_mut() {
+            watcher.set_scanned(&self.load_order);
+        }
+        if let Some(scan) = &self.scan {
+            // restart once the running scan stopped
+            scan.progress.cancel();
+            self.rescan = true;
+            return;
+        }
+
+        let mut engine = std::mem::take(&mut self.engine);
+        engine.set_index_vanilla(self.check_vanilla);
+        let game_path = self.game_path.clone();
+        let load_order = self.load_order.clone();
+        let progress = Arc::new(ScanProgress::default());
+        let thread_progress = progress.clone();
+        let handle = thread::spawn(move || {
+            let report = engine.generate_conflict_map_with_progress(
+                &game_path,
+                &load_order,
+                &thread_progress,
+            );
+            (engine, report)
+        });
+
+        self.scan = Some(ScanTask { progress, handle });
+    }
+
+    /// Swap in the results of a finished background scan
+    fn poll_scan(&mut self) {
+        if !self.scan.as_ref().is_some_and(|s| s.handle.is_finished()) {
+            return;
+        }
+
+        if let Some(scan) = self.scan.take() {
+            match scan.handle.join() {
+                Ok((engine, report)) => {
+                    self.engine = engine;
+                    // cancelled scans keep the previous results
+                    if let Some(report) = report {
+                        self.report = report;
+                        self.conflict_matrix = None;
+                        self.file_tree = None;
+                        self.count_unresolved();
+                        self.check_pins();
+                    }
+                }
+                Err(_) => {
+                    error!("conflict scan failed");
+                    self.engine = create_engine();
+                }
+            }
+        }
+
+        if self.rescan {
+            self.rescan = false;
+            self.generate_conflict_map();
         }
+    }
 
-        //temp_load_order.reverse();
-        self.conflicts = conflicts;
+    /// Cancel a running background scan
+    fn cancel_scan(&mut self) {
+        if let Some(scan) = &self.scan {
+            scan.progress.cancel();
+        }
+        self.rescan = false;
     }
 
     /// get modilist.txt path
     pub fn get_modlist_path(&self) -> PathBuf {
-        self.game_path.join("modlist.txt")
+        get_modlist_path(&self.game_path)
     }
 
     /// Clear and regenerate load order
     pub fn reload_load_order(&mut self) {
-        self.load_order.clear();
+        self.load_order = get_load_order(&self.game_path);
+        self.disabled = get_disabled_archives(&self.game_path);
+        self.patch = PatchManifest::load(&self.game_path);
+        self.check_modlist();
+    }
 
-        let mut mods: Vec<PathBuf> = get_files(&self.game_path, "archive");
+    /// Move an archive to the disabled folder and leave it out of the load order
+    fn disable_archive(&mut self, archive_name: &str) {
+        if let Err(e) = disable_archive(&self.game_path, archive_name) {
+            error!("Failed to disable {}: {}", archive_name, e);
+            return;
+        }
+        if let Some(position) = self.load_order.iter().position(|n| n == archive_name) {
+            self.disabled_positions
+                .insert(archive_name.to_owned(), position);
+        }
 
-        // load order
-        mods.sort_by(|a, b| {
-            a.to_string_lossy()
-                .as_bytes()
-                .cmp(b.to_string_lossy().as_bytes())
-        });
+        let load_order = self
+            .load_order
+            .iter()
+            .filter(|n| *n != archive_name)
+            .cloned()
+            .collect::<Vec<_>>();
+        self.set_load_order(&load_order);
+        self.disabled = get_disabled_archives(&self.game_path);
+    }
 
-        // load according to modlist.txt
-        let mut final_order: Vec<PathBuf> = vec![];
+    /// Move a disabled archive back to its previous position in the load order
+    fn enable_archive(&mut self, archive_name: &str) {
+        if let Err(e) = enable_archive(&self.game_path, archive_name) {
+            error!("Failed to enable {}: {}", archive_name, e);
+            return;
+        }
 
-        if let Ok(lines) = read_file_to_vec(&self.get_modlist_path()) {
-            for name in lines {
-                let file_name = self.game_path.join(name);
-                if mods.contains(&file_name) {
-                    final_order.push(file_name.to_owned());
-                }
-            }
-            // add remaining mods last
-            for m in mods {
-                if !final_order.contains(&m) {
-                    final_order.push(m);
-                }
+        let mut load_order = self.load_order.clone();
+        let position = self
+            .disabled_positions
+            .remove(archive_name)
+            .unwrap_or(load_order.len())
+            .min(load_order.len());
+        load_order.insert(position, archive_name.to_owned());
+        self.set_load_order(&load_order);
+        self.disabled = get_disabled_archives(&self.game_path);
+    }
+
+    /// Validate modlist.txt against the archives in the folder
+    fn check_modlist(&mut self) {
+        self.modlist_issues = match Modlist::read(&self.get_modlist_path()) {
+            Ok(modlist) => modlist.validate(&get_archive_names(&self.game_path)),
+            Err(_) => vec![],
+        };
+    }
+
+    /// Fix a problem in modlist.txt, or all problems if `None`
+    fn fix_modlist(&mut self, issue: Option<&EModlistIssue>) {
+        let modlist_path = self.get_modlist_path();
+        let mut modlist = match Modlist::read(&modlist_path) {
+            Ok(modlist) => modlist,
+            Err(e) => {
+                error!("Failed to read {}: {}", modlist_path.display(), e);
+                return;
             }
-        } else {
-            final_order = mods;
+        };
+        match issue {
+            Some(issue) => modlist.fix(issue),
+            None => modlist.fix_all(&get_archive_names(&self.game_path)),
+        }
+
+        self.backup_modlist();
+        if let Err(e) = modlist.write(&modlist_path) {
+            error!("Failed to write {}: {}", modlist_path.display(), e);
         }
-        // TODO Redmods
 
-        self.load_order = pathbuf_to_string_vec(final_order);
+        self.history.record(self.load_order.clone());
+        self.reload_load_order();
+        self.generate_conflict_map();
+        self.last_load_order = Some(self.load_order.clone());
+        self.save_profile();
     }
 
-    fn serialize_load_order(&self) {
+    fn serialize_load_order(&mut self) {
         if !self.enable_modlist {
             return;
         }
 
+        self.backup_modlist();
         if let Ok(mut file) = std::fs::File::create(self.get_modlist_path()) {
-            for line in &self.load_order {
+            // REDmods are not part of modlist.txt
+            for line in self.load_order.iter().filter(|n| !is_redmod(n)) {
                 let new_line = format!("{}\r\n", line);
                 match file.write_all(new_line.as_bytes()) {
                     Ok(_) => {}
@@ -244,7 +609,300 @@ impl TemplateApp {
         } else {
             error!("failed to write load order");
         }
+        self.check_modlist();
+    }
+
+    /// Copy modlist.txt to the backup folder before it is overwritten or deleted
+    fn backup_modlist(&self) {
+        let Some(backup_dir) = get_backup_dir(&self.game_path) else {
+            return;
+        };
+        match backup_modlist(&self.get_modlist_path(), &backup_dir) {
+            Ok(Some(path)) => log::info!("Saved modlist.txt backup {}", path.display()),
+            Ok(None) => {}
+            Err(e) => error!("Failed to back up modlist.txt: {}", e),
+        }
+    }
+
+    /// Replace the load order, rewrites modlist.txt and recomputes conflicts.
+    /// Archives that are missing on disk are dropped, new archives are loaded last.
+    fn set_load_order(&mut self, load_order: &[String]) {
+        self.load_order = get_load_order_from(&self.game_path, Some(load_order));
+        self.generate_conflict_map();
+        self.last_load_order = Some(self.load_order.clone());
+        self.serialize_load_order();
+        self.save_profile();
+    }
+
+    /// Go back to the load order before the last edit
+    fn undo(&mut self) {
+        if let Some(load_order) = self.history.undo(self.load_order.clone()) {
+            self.set_load_order(&load_order);
+        }
+    }
+
+    /// Apply the last undone load order edit again
+    fn redo(&mut self) {
+        if let Some(load_order) = self.history.redo(self.load_order.clone()) {
+            self.set_load_order(&load_order);
+        }
+    }
+
+    /// Apply a modlist.txt backup
+    fn restore_backup(&mut self, backup: &ModlistBackup) {
+        self.history.record(self.load_order.clone());
+        self.set_load_order(&backup.lines);
     }
+
+    /// Sort the load order by the rules file
+    fn sort_by_rules(&mut self) {
+        let Some(rules_path) = get_rules_file_path() else {
+            return;
+        };
+        let rules = match LoadOrderRules::load(&rules_path) {
+            Ok(rules) => rules,
+            Err(e) => {
+                self.sort_warnings =
+                    vec![format!("Failed to read {}: {}", rules_path.display(), e)];
+                return;
+            }
+        };
+
+        let result = sort_load_order(&self.load_order, &rules);
+        self.sort_warnings = result.warnings;
+        if result.load_order != self.load_order {
+            self.history.record(self.load_order.clone());
+            self.set_load_order(&result.load_order);
+        }
+    }
+
+    /// Open the rules file in the default editor, a template is created if there is none
+    fn open_rules_file(&self) {
+        let Some(rules_path) = get_rules_file_path() else {
+            return;
+        };
+        if !rules_path.exists() {
+            if let Some(parent) = rules_path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Err(e) = fs::write(&rules_path, RULES_TEMPLATE) {
+                error!("Failed to write {}: {}", rules_path.display(), e);
+                return;
+            }
+        }
+        let _ = open::that(rules_path);
+    }
+
+    /// Save the load order to the active profile
+    fn save_profile(&mut self) {
+        if !self.enable_modlist {
+            return;
+        }
+        if self.active_profile.is_empty() {
+            self.active_profile = DEFAULT_PROFILE.to_owned();
+        }
+        self.profiles
+            .insert(self.active_profile.clone(), self.load_order.clone());
+        self.profile_diffs = None;
+    }
+
+    /// Apply the load order of a profile, rewrites modlist.txt and recomputes conflicts
+    fn switch_profile(&mut self, name: &str) {
+        let Some(order) = self.profiles.get(name).cloned() else {
+            return;
+        };
+        self.history.record(self.load_order.clone());
+        self.active_profile = name.to_owned();
+        self.set_load_order(&order);
+    }
+
+    /// Copy the current load order to a new profile and make it active
+    fn duplicate_profile(&mut self) {
+        let base = if self.new_profile_name.trim().is_empty() {
+            self.active_profile.clone()
+        } else {
+            std::mem::take(&mut self.new_profile_name)
+        };
+        self.active_profile = get_unique_profile_name(&self.profiles, &base);
+        self.save_profile();
+    }
+
+    /// Delete the active profile and switch to another one
+    fn delete_profile(&mut self) {
+        if self.profiles.len() < 2 {
+            return;
+        }
+        self.profiles.remove(&self.active_profile);
+        self.profile_diffs = None;
+        if let Some(name) = self.profiles.keys().next().cloned() {
+            self.switch_profile(&name);
+        }
+    }
+}
+
+/// Get the folder of the persistent archive index, next to the app state
+#[cfg(feature = "gui")]
+pub fn get_index_dir_path() -> Option<PathBuf> {
+    eframe::storage_dir(APP_NAME).map(|dir| dir.join("index"))
+}
+
+/// Create an engine that keeps archive entry lists between app starts
+#[cfg(feature = "gui")]
+pub fn create_engine() -> ConflictEngine {
+    match get_index_dir_path() {
+        Some(index_dir) => ConflictEngine::with_index_dir(&index_dir),
+        None => ConflictEngine::new(),
+    }
+}
+
+/// Get the path of the load order rules file, next to the app state
+#[cfg(feature = "gui")]
+pub fn get_rules_file_path() -> Option<PathBuf> {
+    eframe::storage_dir(APP_NAME).map(|dir| dir.join("rules.yaml"))
+}
+
+/// Get the folder for modlist.txt backups of an archive folder, next to the app state
+#[cfg(feature = "gui")]
+pub fn get_backup_dir(game_path: &Path) -> Option<PathBuf> {
+    eframe::storage_dir(APP_NAME).map(|dir| {
+        dir.join("modlist_backups")
+            .join(format!("{:016x}", red4lib::fnv1a64_hash_path(game_path)))
+    })
+}
+
+/// Get the path of the user hash list, next to the app state
+#[cfg(feature = "gui")]
+pub fn get_user_hashes_path() -> Option<PathBuf> {
+    eframe::storage_dir(APP_NAME).map(|dir| dir.join("user_hashes.csv"))
+}
+
+/// Load the hash lists imported by the user
+#[cfg(feature = "gui")]
+pub fn create_user_hash_store() -> UserHashStore {
+    match get_user_hashes_path() {
+        Some(path) => UserHashStore::load(&path),
+        None => UserHashStore::default(),
+    }
+}
+
+/// get modilist.txt path of an archive folder
+pub fn get_modlist_path(game_path: &Path) -> PathBuf {
+    game_path.join("modlist.txt")
+}
+
+/// Get the load order of an archive folder, highest priority first.
+/// Archives listed in modlist.txt come first, all other archives are sorted binary-alphabetically.
+pub fn get_load_order(game_path: &Path) -> Vec<String> {
+    let modlist = read_file_to_vec(&get_modlist_path(game_path)).ok();
+    get_load_order_from(game_path, modlist.as_deref())
+}
+
+/// Get the load order of an archive folder, highest priority first.
+/// Archives in `order` come first, all other archives are sorted binary-alphabetically.
+pub fn get_load_order_from(game_path: &Path, order: Option<&[String]>) -> Vec<String> {
+    let mut mods: Vec<PathBuf> = get_files(game_path, "archive");
+
+    // load order
+    mods.sort_by(|a, b| {
+        a.to_string_lossy()
+            .as_bytes()
+            .cmp(b.to_string_lossy().as_bytes())
+    });
+
+    // load according to modlist.txt
+    let mut final_order: Vec<PathBuf> = vec![];
+
+    if let Some(lines) = order {
+        for name in lines {
+            let file_name = game_path.join(name);
+            if mods.contains(&file_name) {
+                final_order.push(file_name.to_owned());
+            }
+        }
+        // add remaining mods last
+        for m in mods {
+            if !final_order.contains(&m) {
+                final_order.push(m);
+            }
+        }
+    } else {
+        final_order = mods;
+    }
+
+    let mut load_order = pathbuf_to_string_vec(final_order);
+    // REDmods are strictly loaded after archives in the /archive/pc/mod folder
+    load_order.extend(get_redmod_archives(game_path));
+    load_order
+}
+
+/// Get the file names of all archives in an archive folder
+pub fn get_archive_names(game_path: &Path) -> Vec<String> {
+    pathbuf_to_string_vec(get_files(game_path, "archive"))
+}
+
+/// Get the game root folder from an /archive/pc/mod folder
+pub fn get_game_root(game_path: &Path) -> Option<PathBuf> {
+    let components = ["mod", "pc", "archive"];
+    let mut path = game_path;
+    for component in components {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name != component {
+            return None;
+        }
+        path = path.parent()?;
+    }
+    Some(path.to_path_buf())
+}
+
+/// REDmod archives have a path relative to the game root, regular archives only a file name
+pub fn is_redmod(archive_name: &str) -> bool {
+    archive_name.contains('/')
+}
+
+/// Get the full path of an archive in the load order
+pub fn get_archive_path(game_path: &Path, archive_name: &str) -> PathBuf {
+    if is_redmod(archive_name) {
+        if let Some(root) = get_game_root(game_path) {
+            return root.join(archive_name);
+        }
+    }
+    game_path.join(archive_name)
+}
+
+/// Get REDmod archives in the order REDmod deploys them:
+/// mod folders binary-alphabetically, then the archives of each mod binary-alphabetically
+pub fn get_redmod_archives(game_path: &Path) -> Vec<String> {
+    let mut archives = vec![];
+    let Some(root) = get_game_root(game_path) else {
+        return archives;
+    };
+
+    for mod_name in get_redmod_names(&root) {
+        let mut files = pathbuf_to_string_vec(get_files(
+            &root.join("mods").join(&mod_name).join("archives"),
+            "archive",
+        ));
+        files.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        for file in files {
+            archives.push(format!("mods/{}/archives/{}", mod_name, file));
+        }
+    }
+
+    archives
+}
+
+/// Get the REDmod folder names of a game root binary-alphabetically
+pub fn get_redmod_names(root: &Path) -> Vec<String> {
+    let mut mod_names = vec![];
+    if let Ok(entries) = fs::read_dir(root.join("mods")) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                mod_names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    mod_names.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+    mod_names
 }
 
 fn read_file_to_vec(file_path: &PathBuf) -> io::Result<Vec<String>> {
@@ -289,3 +947,58 @@ fn get_files(folder_path: &Path, extension: &str) -> Vec<PathBuf> {
 
     files
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_order_with_redmods() {
+        let root = std::env::temp_dir().join(format!("red4-conflicts-test-{}", std::process::id()));
+        let game_path = root.join("archive").join("pc").join("mod");
+        fs::create_dir_all(&game_path).unwrap();
+        for name in ["a.archive", "b.archive", "c.archive", "readme.txt"] {
+            fs::write(game_path.join(name), "").unwrap();
+        }
+        for (mod_name, name) in [
+            ("z_mod", "x.archive"),
+            ("a_mod", "y.archive"),
+            ("a_mod", "b.archive"),
+        ] {
+            let folder = root.join("mods").join(mod_name).join("archives");
+            fs::create_dir_all(&folder).unwrap();
+            fs::write(folder.join(name), "").unwrap();
+        }
+
+        let without_modlist = get_load_order(&game_path);
+        fs::write(
+            game_path.join("modlist.txt"),
+            "c.archive\r\nmissing.archive\r\na.archive\r\n",
+        )
+        .unwrap();
+        let with_modlist = get_load_order(&game_path);
+        fs::remove_dir_all(&root).unwrap();
+
+        let redmods = [
+            "mods/a_mod/archives/b.archive",
+            "mods/a_mod/archives/y.archive",
+            "mods/z_mod/archives/x.archive",
+        ];
+        assert_eq!(
+            with_modlist,
+            ["c.archive", "a.archive", "b.archive"]
+                .iter()
+                .chain(redmods.iter())
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            without_modlist,
+            ["a.archive", "b.archive", "c.archive"]
+                .iter()
+                .chain(redmods.iter())
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,13 +1,111 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use red4_conflicts::export::{export_report, EExportFormat};
+use red4_conflicts::extract::{extract_versions, get_file_hash};
+use red4_conflicts::install::{find_installs, resolve_archive_folder, validate_archive_folder};
+use red4_conflicts::{create_engine, create_user_hash_store, get_load_order, APP_NAME};
+use red4lib::get_red4_hashes;
+
 const CARGO_NAME: &str = env!("CARGO_PKG_NAME");
 
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// check a folder with archives for conflicts without opening a window
+    Scan {
+        /// Path to the game or a folder with archives to check, a detected game install is used if not given
+        path: Option<PathBuf>,
+
+        /// Write the report as json to this file instead of printing it
+        #[arg(short, long, conflicts_with_all = ["output", "format"])]
+        json: Option<PathBuf>,
+
+        /// Write the report to this file instead of printing it, the format is taken from the extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Format of the --output file
+        #[arg(short, long, value_enum)]
+        format: Option<EExportFormat>,
+
+        /// Leave out conflicts where all archives contain the same file content
+        #[arg(long)]
+        hide_identical: bool,
+
+        /// Also read the base game archives to count the files each archive overrides
+        #[arg(long)]
+        vanilla: bool,
+    },
+    /// write the version of a file from every archive that contains it into a folder per archive
+    Extract {
+        /// Depot path or hash of the file
+        file: String,
+
+        /// Path to the game or a folder with archives, a detected game install is used if not given
+        path: Option<PathBuf>,
+
+        /// Folder to write the versions to
+        #[arg(short, long, default_value = "extracted")]
+        output: PathBuf,
+    },
+}
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
     let _ = simple_logging::log_to_file(format!("{}.log", CARGO_NAME), log::LevelFilter::Info);
 
+    let cli = Cli::parse();
+    if cli.command.is_some() {
+        attach_console();
+    }
+    let result = match cli.command {
+        Some(Commands::Scan {
+            path,
+            json,
+            output,
+            format,
+            hide_identical,
+            vanilla,
+        }) => {
+            // --json is short for --output with the json format
+            let output = match json {
+                Some(json) => Some((json, EExportFormat::Json)),
+                None => output.map(|o| {
+                    let format = format
+                        .or_else(|| EExportFormat::from_path(&o))
+                        .unwrap_or(EExportFormat::Json);
+                    (o, format)
+                }),
+            };
+            Some(run_scan(path, output, hide_identical, vanilla))
+        }
+        Some(Commands::Extract { file, path, output }) => Some(run_extract(&file, path, &output)),
+        None => None,
+    };
+    if let Some(result) = result {
+        // scripts need the error and a failing exit code
+        if let Err(e) = result {
+            log::error!("{}", e);
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -21,8 +119,95 @@ fn main() -> eframe::Result<()> {
     };
 
     eframe::run_native(
-        "Red4 Conflict Checker",
+        APP_NAME,
         native_options,
         Box::new(|cc| Ok(Box::new(red4_conflicts::TemplateApp::new(cc)))),
     )
 }
+
+/// Attach to the console the app was started from, release builds on Windows have none of their own
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // fails if there is no parent console or one is attached already, then output stays where it was
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Archive folder of the given path, or of a detected game install or the current directory if no path is given
+fn get_input_folder(path: Option<PathBuf>) -> Result<PathBuf, String> {
+    let path = match path {
+        Some(path) if !path.exists() => {
+            return Err(format!("{} does not exist", path.display()));
+        }
+        Some(path) => path,
+        None => match find_installs().first() {
+            Some(install) => install.to_path_buf(),
+            None => env::current_dir().map_err(|e| format!("No input path found: {}", e))?,
+        },
+    };
+    // accept the game install root as well
+    let path = resolve_archive_folder(&path);
+    for warning in validate_archive_folder(&path) {
+        log::warn!("{}", warning);
+    }
+    Ok(path)
+}
+
+/// Headless conflict check
+fn run_scan(
+    path: Option<PathBuf>,
+    output: Option<(PathBuf, EExportFormat)>,
+    hide_identical: bool,
+    vanilla: bool,
+) -> Result<(), String> {
+    let path = get_input_folder(path)?;
+
+    log::info!("Scanning {} ...", path.display());
+    let mut engine = create_engine();
+    engine.set_index_vanilla(vanilla);
+    let mut hashes = get_red4_hashes();
+    hashes.extend(create_user_hash_store().get_hashes().clone());
+    let report = red4_conflicts::cli::scan(&mut engine, &path, &hashes, hide_identical);
+
+    if let Some((output_path, format)) = output {
+        export_report(&report, format, &output_path)
+            .map_err(|e| format!("Failed to write report: {}", e))?;
+        log::info!("Wrote report to {}", output_path.display());
+    } else {
+        red4_conflicts::cli::print_report(&report);
+    }
+    Ok(())
+}
+
+/// Headless extraction of all versions of a file
+fn run_extract(file: &str, path: Option<PathBuf>, output: &Path) -> Result<(), String> {
+    let path = get_input_folder(path)?;
+
+    let file_hash = get_file_hash(file);
+    let mut hashes = get_red4_hashes();
+    hashes.extend(create_user_hash_store().get_hashes().clone());
+    if file.trim().parse::<u64>().is_err() {
+        hashes
+            .entry(file_hash)
+            .or_insert_with(|| file.trim().replace('/', "\\"));
+    }
+
+    log::info!("Scanning {} ...", path.display());
+    let report = create_engine().generate_conflict_map(&path, &get_load_order(&path));
+    let files = extract_versions(&path, &report, file_hash, &hashes, output)
+        .map_err(|e| format!("Failed to extract {}: {}", file, e))?;
+    for file in files {
+        log::info!("Extracted {}", file.display());
+        println!("{}", file.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+
+const BOM: &str = "\u{feff}";
+
+/// A problem in modlist.txt, lines are 0-based
+#[derive(Debug, Clone, PartialEq)]
+pub enum EModlistIssue {
+    /// the file starts with a UTF-8 byte order mark
+    Bom,
+    /// number of lines that end in LF or CR instead of CRLF
+    LineEndings(usize),
+    /// leading or trailing whitespace, or an empty line
+    Whitespace { line: usize },
+    /// the line is a path instead of a file name
+    Folder { line: usize, name: String },
+    /// the archive is listed before
+    Duplicate {
+        line: usize,
+        name: String,
+        first_line: usize,
+    },
+    /// an archive with that name exists, but with different case
+    CaseMismatch {
+        line: usize,
+        name: String,
+        file_name: String,
+    },
+    /// no archive with that name exists
+    Missing { line: usize, name: String },
+    /// the archive is not in modlist.txt and loads after all listed archives
+    Unlisted { name: String },
+}
+
+impl fmt::Display for EModlistIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EModlistIssue::Bom => write!(f, "file starts with a byte order mark"),
+            EModlistIssue::LineEndings(count) => {
+                write!(f, "{} lines do not end in CRLF", count)
+            }
+            EModlistIssue::Whitespace { line } => {
+                write!(f, "line {}: empty or stray whitespace", line + 1)
+            }
+            EModlistIssue::Folder { line, name } => write!(
+                f,
+                "line {}: {} contains a folder, only file names are loaded",
+                line + 1,
+                name
+            ),
+            EModlistIssue::Duplicate {
+                line,
+                name,
+                first_line,
+            } => write!(
+                f,
+                "line {}: {} is already listed in line {}",
+                line + 1,
+                name,
+                first_line + 1
+            ),
+            EModlistIssue::CaseMismatch {
+                line,
+                name,
+                file_name,
+            } => write!(
+                f,
+                "line {}: {} differs in case from {}",
+                line + 1,
+                name,
+                file_name
+            ),
+            EModlistIssue::Missing { line, name } => {
+                write!(f, "line {}: {} does not exist", line + 1, name)
+            }
+            EModlistIssue::Unlisted { name } => {
+                write!(
+                    f,
+                    "{} is not listed and loads after all listed archives",
+                    name
+                )
+            }
+        }
+    }
+}
+
+/// Lines of a modlist.txt, with the formatting problems of the file
+#[derive(Debug, Clone, Default)]
+pub struct Modlist {
+    /// lines without line endings
+    pub lines: Vec<String>,
+    pub has_bom: bool,
+    /// number of lines that end in LF or CR instead of CRLF
+    pub bad_line_endings: usize,
+}
+
+impl Modlist {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&String::from_utf8_lossy(&fs::read(path)?)))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let (has_bom, text) = match text.strip_prefix(BOM) {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+
+        let mut lines = vec![];
+        let mut bad_line_endings = 0;
+        let mut pieces = text.split('\n').peekable();
+        while let Some(piece) = pieces.next() {
+            let is_last = pieces.peek().is_none();
+            let (piece, is_crlf) = match piece.strip_suffix('\r') {
+                Some(piece) => (piece, true),
+                None => (piece, false),
+            };
+            // lone CRs split lines as well
+            let parts = piece.split('\r').collect::<Vec<_>>();
+            bad_line_endings += parts.len() - 1;
+            if !is_last && !is_crlf {
+                bad_line_endings += 1;
+            }
+            if is_last && parts == [""] {
+                break;
+            }
+            lines.extend(parts.into_iter().map(|p| p.to_owned()));
+        }
+
+        Self {
+            lines,
+            has_bom,
+            bad_line_endings,
+        }
+    }
+
+    /// Write the lines with CRLF line endings
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut text = String::new();
+        if self.has_bom {
+            text.push_str(BOM);
+        }
+        for line in &self.lines {
+            text.push_str(line);
+            text.push_str("\r\n");
+        }
+        fs::write(path, text)
+    }
+
+    /// Check the lines against the archive file names of the folder
+    pub fn validate(&self, archive_names: &[String]) -> Vec<EModlistIssue> {
+        let mut issues = vec![];
+        if self.has_bom {
+            issues.push(EModlistIssue::Bom);
+        }
+        if self.bad_line_endings > 0 {
+            issues.push(EModlistIssue::LineEndings(self.bad_line_endings));
+        }
+
+        // lowercase name to first line
+        let mut listed: HashMap<String, usize> = HashMap::default();
+        for (line, text) in self.lines.iter().enumerate() {
+            let trimmed = text.trim();
+            if trimmed != text || trimmed.is_empty() {
+                issues.push(EModlistIssue::Whitespace { line });
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let mut name = trimmed;
+            if let Some(file_name) = get_file_name(trimmed) {
+                issues.push(EModlistIssue::Folder {
+                    line,
+                    name: trimmed.to_owned(),
+                });
+                name = file_name;
+            }
+
+            if let Some(first_line) = listed.get(&name.to_lowercase()) {
+                issues.push(EModlistIssue::Duplicate {
+                    line,
+                    name: name.to_owned(),
+                    first_line: *first_line,
+                });
+                continue;
+            }
+            listed.insert(name.to_lowercase(), line);
+
+            if archive_names.iter().any(|a| a == name) {
+                continue;
+            }
+            match archive_names.iter().find(|a| a.eq_ignore_ascii_case(name)) {
+                Some(file_name) => issues.push(EModlistIssue::CaseMismatch {
+                    line,
+                    name: name.to_owned(),
+                    file_name: file_name.to_owned(),
+                }),
+                None => issues.push(EModlistIssue::Missing {
+                    line,
+                    name: name.to_owned(),
+                }),
+            }
+        }
+
+        for name in archive_names {
+            if !listed.contains_key(&name.to_lowercase()) {
+                issues.push(EModlistIssue::Unlisted {
+                    name: name.to_owned(),
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Fix a single issue, line numbers must be from a validation of this modlist
+    pub fn fix(&mut self, issue: &EModlistIssue) {
+        match issue {
+            EModlistIssue::Bom => self.has_bom = false,
+            // lines are always written with CRLF
+            EModlistIssue::LineEndings(_) => self.bad_line_endings = 0,
+            EModlistIssue::Whitespace { line } => {
+                if let Some(text) = self.lines.get_mut(*line) {
+                    *text = text.trim().to_owned();
+                    if text.is_empty() {
+                        self.lines.remove(*line);
+                    }
+                }
+            }
+            EModlistIssue::Folder { line, .. } => {
+                if let Some(text) = self.lines.get_mut(*line) {
+                    if let Some(file_name) = get_file_name(text.trim()) {
+                        *text = file_name.to_owned();
+                    }
+                }
+            }
+            EModlistIssue::CaseMismatch {
+                line, file_name, ..
+            } => {
+                if let Some(text) = self.lines.get_mut(*line) {
+                    *text = file_name.to_owned();
+                }
+            }
+            EModlistIssue::Duplicate { line, .. } | EModlistIssue::Missing { line, .. } => {
+                if *line < self.lines.len() {
+                    self.lines.remove(*line);
+                }
+            }
+            EModlistIssue::Unlisted { name } => self.lines.push(name.to_owned()),
+        }
+    }
+
+    /// Fix all issues, line numbers change after each fix so we validate again
+    pub fn fix_all(&mut self, archive_names: &[String]) {
+        while let Some(issue) = self.validate(archive_names).first() {
+            self.fix(issue);
+        }
+    }
+}
+
+/// File name of a line with a folder, `None` if the line is a file name
+fn get_file_name(line: &str) -> Option<&str> {
+    line.rsplit(['/', '\\'])
+        .next()
+        .filter(|_| line.contains(['/', '\\']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::names;
+
+    #[test]
+    fn parse_line_endings() {
+        let modlist = Modlist::parse("\u{feff}a.archive\r\nb.archive\nc.archive\rd.archive\r\n");
+        assert!(modlist.has_bom);
+        assert_eq!(
+            modlist.lines,
+            names(&["a.archive", "b.archive", "c.archive", "d.archive"])
+        );
+        assert_eq!(modlist.bad_line_endings, 2);
+
+        let modlist = Modlist::parse("a.archive\r\nb.archive");
+        assert!(!modlist.has_bom);
+        assert_eq!(modlist.lines, names(&["a.archive", "b.archive"]));
+        assert_eq!(modlist.bad_line_endings, 0);
+    }
+
+    #[test]
+    fn validate() {
+        let modlist = Modlist::parse(
+            "a.archive\r\n b.archive\r\nmods\\C.archive\r\nA.archive\r\nmissing.archive\r\n",
+        );
+        let archives = names(&["a.archive", "b.archive", "c.archive", "d.archive"]);
+        assert_eq!(
+            modlist.validate(&archives),
+            vec![
+                EModlistIssue::Whitespace { line: 1 },
+                EModlistIssue::Folder {
+                    line: 2,
+                    name: "mods\\C.archive".to_owned()
+                },
+                EModlistIssue::CaseMismatch {
+                    line: 2,
+                    name: "C.archive".to_owned(),
+                    file_name: "c.archive".to_owned()
+                },
+                EModlistIssue::Duplicate {
+                    line: 3,
+                    name: "A.archive".to_owned(),
+                    first_line: 0
+                },
+                EModlistIssue::Missing {
+                    line: 4,
+                    name: "missing.archive".to_owned()
+                },
+                EModlistIssue::Unlisted {
+                    name: "d.archive".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_all() {
+        let mut modlist = Modlist::parse(
+            "\u{feff}a.archive\n\r\n b.archive\r\nmods\\C.archive\r\nA.archive\r\nmissing.archive\r\n",
+        );
+        let archives = names(&["a.archive", "b.archive", "c.archive", "d.archive"]);
+        modlist.fix_all(&archives);
+
+        assert!(modlist.validate(&archives).is_empty());
+        assert!(!modlist.has_bom);
+        assert_eq!(modlist.bad_line_endings, 0);
+        assert_eq!(modlist.lines, archives);
+    }
+}
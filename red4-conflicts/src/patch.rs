@@ -0,0 +1,160 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extract::{get_relative_path, read_entry},
+    get_archive_path,
+};
+
+/// Archive with the chosen file versions, sorts before all other archives so it wins without modlist.txt
+pub const PATCH_ARCHIVE_NAME: &str = "!!!conflict_patch.archive";
+/// Choices that went into the patch archive, stored next to it
+pub const PATCH_MANIFEST_NAME: &str = "!!!conflict_patch.json";
+
+/// A file that is taken from a specific archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchChoice {
+    pub hash: u64,
+    /// depot path of the file in the patch archive
+    pub path: String,
+    /// name of the archive the file is taken from
+    pub archive: String,
+}
+
+/// The choices of the conflict patch archive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PatchManifest {
+    pub choices: Vec<PatchChoice>,
+}
+
+impl PatchManifest {
+    /// Load the manifest of an archive folder, an empty manifest is returned if there is none
+    pub fn load(game_path: &Path) -> Self {
+        let path = get_manifest_path(game_path);
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match serde_json::from_str(&text) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                log::error!("Failed to read {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, game_path: &Path) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(get_manifest_path(game_path), text)
+    }
+
+    pub fn get_choice(&self, file_hash: u64) -> Option<&PatchChoice> {
+        self.choices.iter().find(|c| c.hash == file_hash)
+    }
+
+    /// Take a file from an archive, replaces an earlier choice for the same file
+    pub fn set_choice(&mut self, file_hash: u64, path: &str, archive: &str) {
+        self.remove_choice(file_hash);
+        self.choices.push(PatchChoice {
+            hash: file_hash,
+            path: path.to_owned(),
+            archive: archive.to_owned(),
+        });
+        self.choices.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    pub fn remove_choice(&mut self, file_hash: u64) -> bool {
+        let len = self.choices.len();
+        self.choices.retain(|c| c.hash != file_hash);
+        self.choices.len() != len
+    }
+}
+
+/// /archive/pc/mod/!!!conflict_patch.archive
+pub fn get_patch_path(game_path: &Path) -> PathBuf {
+    game_path.join(PATCH_ARCHIVE_NAME)
+}
+
+/// /archive/pc/mod/!!!conflict_patch.json
+pub fn get_manifest_path(game_path: &Path) -> PathBuf {
+    game_path.join(PATCH_MANIFEST_NAME)
+}
+
+/// Pack the chosen file versions into the patch archive and save the manifest next to it.
+/// Choices that can not be read are skipped and returned as warnings.
+/// The archive and the manifest are removed if there are no choices.
+pub fn build_patch(game_path: &Path, manifest: &PatchManifest) -> io::Result<Vec<String>> {
+    if manifest.choices.is_empty() {
+        for path in [get_patch_path(game_path), get_manifest_path(game_path)] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        return Ok(vec![]);
+    }
+
+    // the process id keeps two running apps apart
+    let temp_dir = env::temp_dir().join(format!("red4-conflicts-patch-{}", std::process::id()));
+    if temp_dir.exists() {
+        fs::remove_dir_all(&temp_dir)?;
+    }
+    let result = pack_choices(game_path, manifest, &temp_dir);
+    // the temp folder is removed even if packing failed
+    if let Err(e) = fs::remove_dir_all(&temp_dir) {
+        log::warn!("Failed to remove {}: {}", temp_dir.display(), e);
+    }
+    let warnings = result?;
+
+    manifest.save(game_path)?;
+    Ok(warnings)
+}
+
+/// Write the chosen file versions to a folder in `temp_dir` and pack it into the patch archive
+fn pack_choices(
+    game_path: &Path,
+    manifest: &PatchManifest,
+    temp_dir: &Path,
+) -> io::Result<Vec<String>> {
+    // the archive is named after the folder it is packed from
+    let pack_dir = temp_dir.join(PATCH_ARCHIVE_NAME.trim_end_matches(".archive"));
+    fs::create_dir_all(&pack_dir)?;
+
+    let mut warnings = vec![];
+    let mut count = 0;
+    for choice in &manifest.choices {
+        if choice.archive == PATCH_ARCHIVE_NAME {
+            warnings.push(format!(
+                "{} can not be taken from the patch itself",
+                choice.path
+            ));
+            continue;
+        }
+        // the files are read from their archives, not from an earlier patch
+        let archive_path = get_archive_path(game_path, &choice.archive);
+        match read_entry(&archive_path, choice.hash) {
+            Ok(Some(data)) => {
+                let path = pack_dir.join(get_relative_path(&choice.path));
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, data)?;
+                count += 1;
+            }
+            Ok(None) => warnings.push(format!("{} is not in {}", choice.path, choice.archive)),
+            Err(e) => warnings.push(format!("Failed to read {}: {}", choice.archive, e)),
+        }
+    }
+
+    let patch_path = get_patch_path(game_path);
+    if count > 0 {
+        red4lib::archive::create_from_directory_path(&pack_dir, &temp_dir.to_path_buf(), None)?;
+        fs::copy(temp_dir.join(PATCH_ARCHIVE_NAME), &patch_path)?;
+    } else if patch_path.exists() {
+        fs::remove_file(&patch_path)?;
+    }
+    Ok(warnings)
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::{
+    is_redmod,
+    rules::{sort_load_order, LoadOrderRule, LoadOrderRules},
+    ConflictReport,
+};
+
+/// A file that an archive must win, no matter which other archives contain it
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct WinnerPin {
+    pub file: u64,
+    pub archive: String,
+}
+
+/// State of a pin in a conflict report
+#[derive(Debug, Clone, PartialEq)]
+pub enum EPinStatus {
+    /// the pinned archive wins the file
+    Satisfied,
+    /// another archive wins the file
+    Violated(String),
+    /// the file is not in more than one archive anymore
+    NoConflict,
+    /// the pinned archive does not contain the file anymore
+    Missing,
+}
+
+/// Result of solving the load order for a set of pins
+#[derive(Debug, Clone)]
+pub enum EPinSolution {
+    /// a load order in which every pinned archive wins its file
+    Solved(Vec<String>),
+    /// the smallest set of pins that can not be satisfied together
+    Contradiction(Vec<WinnerPin>),
+}
+
+/// Check which pins hold in a conflict report
+pub fn check_pins(report: &ConflictReport, pins: &[WinnerPin]) -> Vec<EPinStatus> {
+    pins.iter()
+        .map(|pin| {
+            let Some(stack) = report.get_stack(&pin.file) else {
+                return EPinStatus::NoConflict;
+            };
+            let names = stack
+                .iter()
+                .map(|h| report.get_archive_name(h))
+                .collect::<Vec<_>>();
+            match names.last() {
+                _ if !names.contains(&pin.archive) => EPinStatus::Missing,
+                Some(winner) if *winner == pin.archive => EPinStatus::Satisfied,
+                Some(winner) => EPinStatus::Violated(winner.to_owned()),
+                None => EPinStatus::NoConflict,
+            }
+        })
+        .collect()
+}
+
+/// Compute a load order in which every pinned archive wins its file.
+/// Archives that no pin moves keep their relative order, REDmods stay last in deploy order.
+/// Pins of files that are not in conflict anymore are ignored.
+pub fn solve_pins(
+    load_order: &[String],
+    report: &ConflictReport,
+    pins: &[WinnerPin],
+) -> EPinSolution {
+    let position = |name: &str| load_order.iter().position(|n| n == name);
+
+    // edges (winner, loser): the winner has to load before the loser, mapped to the pin that needs it
+    let mut edges: BTreeMap<(String, String), usize> = BTreeMap::default();
+    for (i, pin) in pins.iter().enumerate() {
+        let Some(stack) = report.get_stack(&pin.file) else {
+            continue;
+        };
+        let names = stack
+            .iter()
+            .map(|h| report.get_archive_name(h))
+            .collect::<Vec<_>>();
+        if !names.contains(&pin.archive) {
+            continue;
+        }
+
+        for other in names.iter().filter(|n| **n != pin.archive) {
+            match (is_redmod(&pin.archive), is_redmod(other)) {
+                // REDmods always load after the archives in /archive/pc/mod
+                (false, true) => {}
+                (true, false) => return EPinSolution::Contradiction(vec![pin.clone()]),
+                // REDmods keep their deploy order
+                (true, true) => {
+                    if position(&pin.archive) > position(other) {
+                        return EPinSolution::Contradiction(vec![pin.clone()]);
+                    }
+                }
+                (false, false) => {
+                    edges
+                        .entry((pin.archive.clone(), other.clone()))
+                        .or_insert(i);
+                }
+            }
+        }
+    }
+
+    if let Some(cycle) = find_shortest_cycle(&edges) {
+        let contradiction = cycle
+            .iter()
+            .map(|pin| pins[*pin].clone())
+            .collect::<BTreeSet<_>>();
+        return EPinSolution::Contradiction(contradiction.into_iter().collect());
+    }
+
+    // the rules sort keeps the current order where no pin applies
+    let mut losers: BTreeMap<String, Vec<String>> = BTreeMap::default();
+    for (winner, loser) in edges.keys() {
+        losers
+            .entry(winner.clone())
+            .or_default()
+            .push(loser.clone());
+    }
+    let rules = LoadOrderRules {
+        groups: vec![],
+        rules: losers
+            .into_iter()
+            .map(|(archive, before)| LoadOrderRule {
+                archive,
+                after: vec![],
+                before,
+            })
+            .collect(),
+    };
+    EPinSolution::Solved(sort_load_order(load_order, &rules).load_order)
+}
+
+/// Pins on the edges of the shortest cycle, if there is one.
+/// Each archive of a cycle needs its own pin, so no smaller set of pins forms a cycle.
+fn find_shortest_cycle(edges: &BTreeMap<(String, String), usize>) -> Option<Vec<usize>> {
+    let mut successors: BTreeMap<&str, Vec<&str>> = BTreeMap::default();
+    for (winner, loser) in edges.keys() {
+        successors.entry(winner).or_default().push(loser);
+    }
+
+    let mut shortest: Option<Vec<&str>> = None;
+    for start in successors.keys() {
+        // breadth first search back to the start
+        let mut parents: HashMap<&str, &str> = HashMap::default();
+        let mut queue = VecDeque::from([*start]);
+        let mut end = None;
+        'search: while let Some(node) = queue.pop_front() {
+            for next in successors.get(node).into_iter().flatten() {
+                if next == start {
+                    end = Some(node);
+                    break 'search;
+                }
+                if !parents.contains_key(next) {
+                    parents.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let Some(mut node) = end else {
+            continue;
+        };
+        let mut cycle = vec![node];
+        while node != *start {
+            node = parents[node];
+            cycle.push(node);
+        }
+        cycle.reverse();
+        if shortest.as_ref().is_none_or(|s| cycle.len() < s.len()) {
+            shortest = Some(cycle);
+        }
+    }
+
+    let cycle = shortest?;
+    Some(
+        cycle
+            .iter()
+            .zip(cycle.iter().cycle().skip(1))
+            .map(|(winner, loser)| edges[&(winner.to_string(), loser.to_string())])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{compute_conflicts, ArchiveViewModel};
+
+    /// load order from highest to lowest priority with the files of each archive
+    fn setup(archives: &[(&str, &[u64])]) -> (Vec<String>, ConflictReport) {
+        let load_order = archives.iter().map(|(n, _)| n.to_string()).collect();
+        let vms = archives
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, (name, files))| {
+                let vm = ArchiveViewModel {
+                    file_name: name.to_string(),
+                    files: files.to_vec(),
+                    ..Default::default()
+                };
+                (i as u64 + 1, vm)
+            })
+            .collect();
+        (load_order, compute_conflicts(vms))
+    }
+
+    fn pin(file: u64, archive: &str) -> WinnerPin {
+        WinnerPin {
+            file,
+            archive: archive.to_owned(),
+        }
+    }
+
+    #[test]
+    fn solve() {
+        let (load_order, report) = setup(&[
+            ("a", &[10]),
+            ("b", &[]),
+            ("c", &[10]),
+            ("d", &[]),
+            ("mods/m/archives/e", &[10]),
+        ]);
+        let pins = [pin(10, "c"), pin(99, "a")];
+        let EPinSolution::Solved(solved) = solve_pins(&load_order, &report, &pins) else {
+            panic!("pins are not solved");
+        };
+        assert_eq!(solved, ["b", "c", "a", "d", "mods/m/archives/e"]);
+    }
+
+    #[test]
+    fn two_pins_contradict() {
+        let (load_order, report) = setup(&[("a", &[10, 20]), ("b", &[10, 20])]);
+        let pins = [pin(10, "a"), pin(20, "b")];
+        let EPinSolution::Contradiction(contradiction) = solve_pins(&load_order, &report, &pins)
+        else {
+            panic!("pins do not contradict");
+        };
+        assert_eq!(contradiction, pins);
+    }
+
+    #[test]
+    fn three_pins_contradict() {
+        let (load_order, report) = setup(&[
+            ("a", &[10, 30, 40]),
+            ("b", &[10, 20]),
+            ("c", &[20, 30]),
+            ("d", &[40]),
+        ]);
+        let pins = [pin(10, "b"), pin(20, "c"), pin(30, "a"), pin(40, "a")];
+        let EPinSolution::Contradiction(contradiction) = solve_pins(&load_order, &report, &pins)
+        else {
+            panic!("pins do not contradict");
+        };
+        assert_eq!(contradiction, [pin(10, "b"), pin(20, "c"), pin(30, "a")]);
+    }
+
+    #[test]
+    fn shortest_cycle_is_reported() {
+        // a → c → b → a and a → d → a
+        let (load_order, report) = setup(&[
+            ("a", &[10, 30, 40, 50]),
+            ("b", &[10, 20]),
+            ("c", &[20, 30]),
+            ("d", &[40, 50]),
+        ]);
+        let pins = [
+            pin(10, "b"),
+            pin(20, "c"),
+            pin(30, "a"),
+            pin(40, "a"),
+            pin(50, "d"),
+        ];
+        let EPinSolution::Contradiction(contradiction) = solve_pins(&load_order, &report, &pins)
+        else {
+            panic!("pins do not contradict");
+        };
+        assert_eq!(contradiction, [pin(40, "a"), pin(50, "d")]);
+    }
+
+    #[test]
+    fn redmod_can_not_win_against_archives() {
+        let (load_order, report) = setup(&[
+            ("a", &[10]),
+            ("mods/m/archives/b", &[10, 20]),
+            ("mods/n/archives/c", &[20]),
+        ]);
+
+        let pins = [pin(10, "mods/m/archives/b")];
+        let EPinSolution::Contradiction(contradiction) = solve_pins(&load_order, &report, &pins)
+        else {
+            panic!("pin does not contradict");
+        };
+        assert_eq!(contradiction, pins);
+
+        // REDmods keep their deploy order
+        let pins = [pin(20, "mods/n/archives/c")];
+        assert!(matches!(
+            solve_pins(&load_order, &report, &pins),
+            EPinSolution::Contradiction(_)
+        ));
+        let pins = [pin(20, "mods/m/archives/b")];
+        assert!(matches!(
+            solve_pins(&load_order, &report, &pins),
+            EPinSolution::Solved(_)
+        ));
+    }
+
+    #[test]
+    fn check() {
+        let (_, report) = setup(&[("a", &[10, 20]), ("b", &[10, 20, 30])]);
+        assert_eq!(
+            check_pins(
+                &report,
+                &[pin(10, "a"), pin(20, "b"), pin(30, "b"), pin(10, "c")]
+            ),
+            [
+                EPinStatus::Satisfied,
+                EPinStatus::Violated("a".to_owned()),
+                EPinStatus::NoConflict,
+                EPinStatus::Missing,
+            ]
+        );
+    }
+}
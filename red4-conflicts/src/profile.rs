@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// name of the profile that is created on first start
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// Named load orders
+pub type Profiles = BTreeMap<String, Vec<String>>;
+
+/// Difference of an archive between two load orders
+#[derive(Debug, Clone, PartialEq)]
+pub enum ELoadOrderDiff {
+    /// archive is only in the first load order
+    Removed { name: String, index: usize },
+    /// archive is only in the second load order
+    Added { name: String, index: usize },
+    /// archive is in both load orders at different positions
+    Moved {
+        name: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// Compare two load orders, indices are positions in the full load orders.
+/// Only the fewest archives are reported as moved: the archives in both load orders
+/// that are not part of their longest common subsequence.
+pub fn compare_load_orders(a: &[String], b: &[String]) -> Vec<ELoadOrderDiff> {
+    let mut result = vec![];
+    let positions_a = a
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let positions_b = b
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.as_str(), i))
+        .collect::<HashMap<_, _>>();
+
+    for (index, name) in a.iter().enumerate() {
+        if !positions_b.contains_key(name.as_str()) {
+            result.push(ELoadOrderDiff::Removed {
+                name: name.to_owned(),
+                index,
+            });
+        }
+    }
+    for (index, name) in b.iter().enumerate() {
+        if !positions_a.contains_key(name.as_str()) {
+            result.push(ELoadOrderDiff::Added {
+                name: name.to_owned(),
+                index,
+            });
+        }
+    }
+
+    // shared archives in the order of the first load order, with their position in the second one
+    let shared = a
+        .iter()
+        .enumerate()
+        .filter_map(|(from, n)| positions_b.get(n.as_str()).map(|to| (n, from, *to)))
+        .collect::<Vec<_>>();
+    let kept = get_longest_increasing(&shared.iter().map(|(_, _, to)| *to).collect::<Vec<_>>());
+    for (i, (name, from, to)) in shared.into_iter().enumerate() {
+        if !kept[i] {
+            result.push(ELoadOrderDiff::Moved {
+                name: name.to_owned(),
+                from,
+                to,
+            });
+        }
+    }
+
+    result
+}
+
+/// Mark a longest strictly increasing subsequence of distinct values, in O(n log n).
+/// For the second load order positions of shared archives, this is their longest common subsequence.
+fn get_longest_increasing(values: &[usize]) -> Vec<bool> {
+    // tails[k]: index of the smallest last value of an increasing subsequence of length k + 1
+    let mut tails: Vec<usize> = vec![];
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+    for (i, value) in values.iter().enumerate() {
+        let k = tails.partition_point(|t| values[*t] < *value);
+        previous[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut kept = vec![false; values.len()];
+    let mut current = tails.last().copied();
+    while let Some(i) = current {
+        kept[i] = true;
+        current = previous[i];
+    }
+    kept
+}
+
+/// Get a profile name that is not used yet, e.g. "name (2)"
+pub fn get_unique_profile_name(profiles: &Profiles, name: &str) -> String {
+    let name = name.trim();
+    let name = if name.is_empty() {
+        DEFAULT_PROFILE
+    } else {
+        name
+    };
+    if !profiles.contains_key(name) {
+        return name.to_owned();
+    }
+
+    let mut i = 2;
+    loop {
+        let candidate = format!("{} ({})", name, i);
+        if !profiles.contains_key(&candidate) {
+            return candidate;
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::names;
+
+    #[test]
+    fn one_archive_moved() {
+        let a = names(&["1", "2", "3", "4", "5"]);
+        let b = names(&["5", "1", "2", "3", "4"]);
+        assert_eq!(
+            compare_load_orders(&a, &b),
+            [ELoadOrderDiff::Moved {
+                name: "5".to_owned(),
+                from: 4,
+                to: 0
+            }]
+        );
+        assert!(compare_load_orders(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_do_not_move() {
+        let a = names(&["1", "2", "3", "4"]);
+        let b = names(&["0", "1", "3", "2", "4"]);
+        assert_eq!(
+            compare_load_orders(&a, &b),
+            [
+                ELoadOrderDiff::Added {
+                    name: "0".to_owned(),
+                    index: 0
+                },
+                ELoadOrderDiff::Moved {
+                    name: "2".to_owned(),
+                    from: 1,
+                    to: 3
+                },
+            ]
+        );
+
+        let b = names(&["1", "3", "4"]);
+        assert_eq!(
+            compare_load_orders(&a, &b),
+            [ELoadOrderDiff::Removed {
+                name: "2".to_owned(),
+                index: 1
+            }]
+        );
+    }
+}
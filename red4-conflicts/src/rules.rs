@@ -0,0 +1,312 @@
+use std::{cmp::Reverse, collections::HashSet, fs, io, path::Path};
+
+use crate::is_redmod;
+
+/// Written when the rules file is opened for the first time
+pub const RULES_TEMPLATE: &str = r#"# Load order rules. Archives at the top of the load order load first and win conflicts.
+# Patterns are matched case-insensitively against archive names and support * and ? wildcards.
+
+# Archives in groups with a higher priority load first, archives in no group have priority 0.
+# Groups are only a preference, rules always take precedence.
+#
+# groups:
+#   - name: patches
+#     priority: 10
+#     patterns: ["*patch*", "!!!*"]
+groups: []
+
+# Archives matching "archive" load after all archives matching "after"
+# and before all archives matching "before".
+#
+# rules:
+#   - archive: "my_mod_patch.archive"
+#     before: ["my_mod.archive"]
+#   - archive: "my_mod_addon*.archive"
+#     after: ["my_mod.archive"]
+rules: []
+"#;
+
+/// A named set of archives with a load order priority
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct RuleGroup {
+    pub name: String,
+    /// higher priorities load first
+    pub priority: i32,
+    pub patterns: Vec<String>,
+}
+
+/// Archives matching `archive` load after all archives matching `after` and before all matching `before`
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct LoadOrderRule {
+    pub archive: String,
+    pub after: Vec<String>,
+    pub before: Vec<String>,
+}
+
+/// Contents of a load order rules file
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct LoadOrderRules {
+    pub groups: Vec<RuleGroup>,
+    pub rules: Vec<LoadOrderRule>,
+}
+
+/// A sorted load order and the problems found while sorting
+#[derive(Debug, Clone, Default)]
+pub struct SortResult {
+    pub load_order: Vec<String>,
+    /// cycles, rules that could not be satisfied and patterns that match nothing
+    pub warnings: Vec<String>,
+}
+
+impl LoadOrderRules {
+    /// Read a yaml rules file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_yaml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Priority of the first group that contains the archive
+    pub fn get_priority(&self, archive_name: &str) -> i32 {
+        self.groups
+            .iter()
+            .find(|g| g.patterns.iter().any(|p| glob_match(p, archive_name)))
+            .map(|g| g.priority)
+            .unwrap_or_default()
+    }
+}
+
+/// Sort a load order so that all rules are satisfied.
+/// Archives that no rule or group moves keep their relative order, REDmods stay last.
+pub fn sort_load_order(load_order: &[String], rules: &LoadOrderRules) -> SortResult {
+    let (redmods, archives): (Vec<String>, Vec<String>) =
+        load_order.iter().cloned().partition(|n| is_redmod(n));
+    let count = archives.len();
+    let mut warnings = vec![];
+
+    let matching = |pattern: &str| {
+        (0..count)
+            .filter(|i| glob_match(pattern, &archives[*i]))
+            .collect::<Vec<_>>()
+    };
+
+    // edges (a, b): a loads before b
+    let mut edges: HashSet<(usize, usize)> = HashSet::default();
+    for rule in &rules.rules {
+        let subjects = matching(&rule.archive);
+        if subjects.is_empty() {
+            warnings.push(format!("Rule for \"{}\" matches no archive", rule.archive));
+            continue;
+        }
+        for (pattern, loads_after) in rule
+            .after
+            .iter()
+            .map(|p| (p, true))
+            .chain(rule.before.iter().map(|p| (p, false)))
+        {
+            let others = matching(pattern);
+            if others.is_empty() {
+                warnings.push(format!(
+                    "Rule for \"{}\": \"{}\" matches no archive",
+                    rule.archive, pattern
+                ));
+            }
+            for &s in &subjects {
+                for &o in others.iter().filter(|o| **o != s) {
+                    edges.insert(if loads_after { (o, s) } else { (s, o) });
+                }
+            }
+        }
+    }
+
+    let mut successors = vec![vec![]; count];
+    let mut predecessors = vec![vec![]; count];
+    let mut indegree = vec![0usize; count];
+    for &(a, b) in &edges {
+        successors[a].push(b);
+        predecessors[b].push(a);
+        indegree[b] += 1;
+    }
+
+    // highest group priority first, then the current position
+    let priorities = archives
+        .iter()
+        .map(|a| rules.get_priority(a))
+        .collect::<Vec<_>>();
+    let key = |i: &usize| (Reverse(priorities[*i]), *i);
+
+    let mut placed = vec![false; count];
+    let mut order = vec![];
+    while order.len() < count {
+        let next = (0..count)
+            .filter(|i| !placed[*i] && indegree[*i] == 0)
+            .min_by_key(key);
+        let next = match next {
+            Some(i) => i,
+            None => {
+                // all remaining archives wait on each other
+                let cycle = find_cycle(&placed, &predecessors);
+                warnings.push(format!(
+                    "Rules form a cycle: {}",
+                    cycle
+                        .iter()
+                        .chain(cycle.first())
+                        .map(|i| archives[*i].as_str())
+                        .collect::<Vec<_>>()
+                        .join(" → ")
+                ));
+                let forced = *cycle.iter().min_by_key(|i| key(i)).unwrap_or(&cycle[0]);
+                for p in predecessors[forced].iter().filter(|p| !placed[**p]) {
+                    warnings.push(format!(
+                        "Cannot load {} after {}",
+                        archives[forced], archives[*p]
+                    ));
+                }
+                forced
+            }
+        };
+
+        placed[next] = true;
+        order.push(next);
+        for s in &successors[next] {
+            indegree[*s] = indegree[*s].saturating_sub(1);
+        }
+    }
+
+    let mut load_order = order
+        .into_iter()
+        .map(|i| archives[i].to_owned())
+        .collect::<Vec<_>>();
+    load_order.extend(redmods);
+    SortResult {
+        load_order,
+        warnings,
+    }
+}
+
+/// Find a cycle among the archives that are not placed yet, in load order.
+/// Every remaining archive has a remaining predecessor, so walking predecessors must repeat.
+fn find_cycle(placed: &[bool], predecessors: &[Vec<usize>]) -> Vec<usize> {
+    let Some(start) = placed.iter().position(|p| !p) else {
+        return vec![];
+    };
+
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        let Some(previous) = predecessors[current].iter().find(|p| !placed[**p]) else {
+            return path;
+        };
+        if let Some(pos) = path.iter().position(|n| n == previous) {
+            let mut cycle = path.split_off(pos);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(*previous);
+        current = *previous;
+    }
+}
+
+/// Case-insensitive wildcard match, `*` matches any text and `?` a single character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // position of the last * and the text position it matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // let the last * match one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::names;
+
+    fn rule(archive: &str, after: &[&str], before: &[&str]) -> LoadOrderRule {
+        LoadOrderRule {
+            archive: archive.to_owned(),
+            after: names(after),
+            before: names(before),
+        }
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*.archive", "a.archive"));
+        assert!(glob_match("A?.ARCHIVE", "ab.archive"));
+        assert!(glob_match("*patch*", "my_patch_v2.archive"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+        assert!(!glob_match("*.archive", "a.archive.xl"));
+        assert!(!glob_match("a?c", "abbc"));
+    }
+
+    #[test]
+    fn sort_keeps_unmoved_archives_in_order() {
+        let load_order = names(&["a", "b", "c", "d", "mods/m/archives/e"]);
+        let rules = LoadOrderRules {
+            groups: vec![],
+            rules: vec![rule("d", &[], &["b"]), rule("a", &["c"], &[])],
+        };
+        let result = sort_load_order(&load_order, &rules);
+        assert_eq!(
+            result.load_order,
+            names(&["c", "a", "d", "b", "mods/m/archives/e"])
+        );
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn sort_groups_and_cycles() {
+        let load_order = names(&["a", "b", "patch"]);
+        let rules = LoadOrderRules {
+            groups: vec![RuleGroup {
+                name: "patches".to_owned(),
+                priority: 10,
+                patterns: names(&["*patch*"]),
+            }],
+            rules: vec![],
+        };
+        let result = sort_load_order(&load_order, &rules);
+        assert_eq!(result.load_order, names(&["patch", "a", "b"]));
+
+        let rules = LoadOrderRules {
+            groups: vec![],
+            rules: vec![
+                rule("a", &["b"], &[]),
+                rule("b", &["a"], &[]),
+                rule("x", &[], &[]),
+            ],
+        };
+        let result = sort_load_order(&load_order, &rules);
+        assert_eq!(result.load_order.len(), 3);
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.starts_with("Rules form a cycle")));
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.contains("\"x\" matches no archive")));
+    }
+}
@@ -0,0 +1,6 @@
+//! Helpers shared by the unit tests
+
+/// Owned archive names
+pub fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ConflictReport;
+
+/// folder of files with hashes that are not in the hash list
+pub const UNRESOLVED_FOLDER: &str = "<unresolved>";
+
+/// Files shown in the tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeFilter {
+    /// only files that are in more than one archive
+    pub conflicts_only: bool,
+    /// leave out conflicts where all archives contain the same content
+    pub hide_identical: bool,
+    /// comma separated extensions, e.g. "mesh, ent", empty for all
+    pub extensions: String,
+}
+
+impl TreeFilter {
+    fn get_extensions(&self) -> Vec<String> {
+        self.extensions
+            .split([',', ' ', ';'])
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .filter(|e| !e.is_empty())
+            .collect()
+    }
+}
+
+/// A file of the depot and the archives that contain it
+#[derive(Debug, Clone)]
+pub struct FileNode {
+    pub hash: u64,
+    /// file name without folders
+    pub name: String,
+    /// archive hashes from lowest to highest priority, the last one wins
+    pub archives: Vec<u64>,
+    pub identical: bool,
+}
+
+/// A folder of the depot
+#[derive(Debug, Clone, Default)]
+pub struct FolderNode {
+    pub name: String,
+    pub folders: BTreeMap<String, FolderNode>,
+    /// sorted by name
+    pub files: Vec<FileNode>,
+    /// number of files in this folder and all sub folders
+    pub file_count: usize,
+    /// number of files in more than one archive in this folder and all sub folders
+    pub conflict_count: usize,
+}
+
+/// Build a folder tree of all files in the archives of a report, paths are resolved with `hashes`
+pub fn build_tree(
+    report: &ConflictReport,
+    hashes: &HashMap<u64, String>,
+    filter: &TreeFilter,
+) -> FolderNode {
+    let extensions = filter.get_extensions();
+    let mut root = FolderNode::default();
+
+    // each file once, with its archive stack
+    let mut stacks: HashMap<u64, Vec<u64>> = HashMap::default();
+    for (archive_hash, vm) in &report.archives {
+        for file_hash in vm.get_no_conflicts() {
+            stacks.insert(*file_hash, vec![*archive_hash]);
+        }
+    }
+    for (file_hash, stack) in &report.conflicts {
+        stacks.insert(*file_hash, stack.clone());
+    }
+
+    for (hash, archives) in stacks {
+        let is_conflict = archives.len() > 1;
+        let identical = report.is_identical(&hash);
+        if filter.conflicts_only && !is_conflict {
+            continue;
+        }
+        if filter.hide_identical && identical {
+            continue;
+        }
+
+        let path = match hashes.get(&hash) {
+            Some(path) => path.to_owned(),
+            None => format!("{}\\{}", UNRESOLVED_FOLDER, hash),
+        };
+        let mut parts = path.split(['\\', '/']).collect::<Vec<_>>();
+        let name = parts.pop().unwrap_or_default().to_owned();
+        if !extensions.is_empty() {
+            let extension = name.rsplit_once('.').map(|(_, e)| e.to_lowercase());
+            if !extension.is_some_and(|e| extensions.contains(&e)) {
+                continue;
+            }
+        }
+
+        let mut folder = &mut root;
+        for part in parts {
+            folder.file_count += 1;
+            folder.conflict_count += is_conflict as usize;
+            folder = folder
+                .folders
+                .entry(part.to_owned())
+                .or_insert_with(|| FolderNode {
+                    name: part.to_owned(),
+                    ..Default::default()
+                });
+        }
+        folder.file_count += 1;
+        folder.conflict_count += is_conflict as usize;
+        folder.files.push(FileNode {
+            hash,
+            name,
+            archives,
+            identical,
+        });
+    }
+
+    sort_files(&mut root);
+    root
+}
+
+fn sort_files(folder: &mut FolderNode) {
+    folder.files.sort_by(|a, b| a.name.cmp(&b.name));
+    for child in folder.folders.values_mut() {
+        sort_files(child);
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use red4lib::fnv1a64_hash_path;
+
+/// Depot paths added by the user, persisted as a WolvenKit style `String,Hash` csv
+#[derive(Debug, Default)]
+pub struct UserHashStore {
+    hashes: BTreeMap<u64, String>,
+    /// file to persist the store to
+    path: Option<PathBuf>,
+}
+
+impl UserHashStore {
+    /// Load a persisted store, an empty store is returned if the file does not exist
+    pub fn load(path: &Path) -> Self {
+        let mut store = Self {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        if let Ok(text) = fs::read_to_string(path) {
+            store.hashes = parse_hash_list(&text);
+        }
+        store
+    }
+
+    /// Write the store to its file
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut text = String::from("String,Hash\n");
+        for (hash, name) in &self.hashes {
+            text += &format!("{},{}\n", name, hash);
+        }
+        fs::write(path, text)
+    }
+
+    /// Add the paths of a hash list file, returns the number of new hashes.
+    /// Both WolvenKit `archivehashes.csv` files and plain lists of depot paths are supported.
+    pub fn import(&mut self, path: &Path) -> io::Result<usize> {
+        let text = fs::read_to_string(path)?;
+        let mut count = 0;
+        for (hash, name) in parse_hash_list(&text) {
+            if self.hashes.insert(hash, name).is_none() {
+                count += 1;
+            }
+        }
+        self.save()?;
+        Ok(count)
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.hashes.clear();
+        self.save()
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn get_hashes(&self) -> &BTreeMap<u64, String> {
+        &self.hashes
+    }
+}
+
+/// Parse `path,hash` csv lines or plain depot paths, which are hashed
+pub fn parse_hash_list(text: &str) -> BTreeMap<u64, String> {
+    let mut hashes = BTreeMap::default();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.eq_ignore_ascii_case("string,hash") {
+            continue;
+        }
+
+        if let Some((name, hash)) = line.rsplit_once(',') {
+            if let Ok(hash) = hash.trim().parse::<u64>() {
+                hashes.insert(hash, name.trim().to_owned());
+                continue;
+            }
+        }
+        // depot paths use backslashes
+        let name = line.replace('/', "\\");
+        hashes.insert(fnv1a64_hash_path(Path::new(&name)), name);
+    }
+    hashes
+}
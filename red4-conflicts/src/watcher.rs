@@ -0,0 +1,178 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    cache::get_file_stamp, get_archive_path, get_game_root, get_load_order, xl::get_xl_paths,
+};
+
+/// wait until no more changes come in, mod managers copy files one by one
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches an archive folder and the REDmod folder for changes.
+/// The game root is watched as well to pick up a REDmod folder that is created later.
+pub struct ModWatcher {
+    /// the watched archive folder
+    pub game_path: PathBuf,
+    watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    /// REDmod folder of the game, only set for archive folders of a game install
+    redmod_path: Option<PathBuf>,
+    /// the REDmod folder is watched, it is only created by the first REDmod install
+    is_watching_redmods: bool,
+    /// time of the last relevant change that was not reported yet
+    pending_since: Option<Instant>,
+    /// state of the folder at the last conflict check
+    scanned: Option<FolderState>,
+}
+
+/// Load order and the size and modification time of each archive and .xl file
+#[derive(Debug, PartialEq)]
+struct FolderState {
+    load_order: Vec<String>,
+    stamps: Vec<Option<(u64, u64)>>,
+}
+
+impl FolderState {
+    fn read(game_path: &Path, load_order: &[String]) -> Self {
+        let stamps = load_order
+            .iter()
+            .map(|n| get_archive_path(game_path, n))
+            .chain(get_xl_paths(game_path).into_iter().map(|(_, p)| p))
+            .map(|p| get_file_stamp(&p).ok())
+            .collect();
+        Self {
+            load_order: load_order.to_vec(),
+            stamps,
+        }
+    }
+}
+
+impl ModWatcher {
+    /// Start watching an archive folder, the ui is woken up on changes
+    pub fn new(game_path: &Path, ctx: &egui::Context) -> Option<Self> {
+        let (tx, rx) = channel();
+        let ctx = ctx.clone();
+        let handler = move |event: notify::Result<Event>| {
+            let _ = tx.send(event);
+            ctx.request_repaint();
+        };
+        let mut watcher = match notify::recommended_watcher(handler) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::error!("Failed to create file watcher: {}", e);
+                return None;
+            }
+        };
+
+        if let Err(e) = watcher.watch(game_path, RecursiveMode::NonRecursive) {
+            log::error!("Failed to watch {}: {}", game_path.display(), e);
+            return None;
+        }
+        let game_root = get_game_root(game_path);
+        if let Some(game_root) = &game_root {
+            if let Err(e) = watcher.watch(game_root, RecursiveMode::NonRecursive) {
+                log::error!("Failed to watch {}: {}", game_root.display(), e);
+            }
+        }
+
+        let mut mod_watcher = Self {
+            game_path: game_path.to_path_buf(),
+            watcher,
+            rx,
+            redmod_path: game_root.map(|r| r.join("mods")),
+            is_watching_redmods: false,
+            pending_since: None,
+            scanned: None,
+        };
+        mod_watcher.watch_redmods();
+        Some(mod_watcher)
+    }
+
+    /// Watch the REDmod folder once it exists
+    fn watch_redmods(&mut self) {
+        let Some(redmod_path) = &self.redmod_path else {
+            return;
+        };
+        if self.is_watching_redmods || !redmod_path.is_dir() {
+            return;
+        }
+        match self.watcher.watch(redmod_path, RecursiveMode::Recursive) {
+            Ok(_) => self.is_watching_redmods = true,
+            Err(e) => log::error!("Failed to watch {}: {}", redmod_path.display(), e),
+        }
+    }
+
+    /// Remember the folder state that conflicts are checked for, call after the app changed the folder.
+    /// Changes are only reported if the folder differs from it, so the app's own writes are skipped.
+    pub fn set_scanned(&mut self, load_order: &[String]) {
+        self.scanned = Some(FolderState::read(&self.game_path, load_order));
+    }
+
+    /// Returns true once after the load order, archives or .xl files changed
+    pub fn poll(&mut self) -> bool {
+        let events = self.rx.try_iter().flatten().collect::<Vec<_>>();
+        for event in events {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            // the REDmod folder was created or deleted in the game root
+            if event
+                .paths
+                .iter()
+                .any(|p| Some(p) == self.redmod_path.as_ref())
+            {
+                if matches!(event.kind, EventKind::Remove(_)) {
+                    if let Some(redmod_path) = &self.redmod_path {
+                        let _ = self.watcher.unwatch(redmod_path);
+                    }
+                    self.is_watching_redmods = false;
+                } else {
+                    self.watch_redmods();
+                }
+            }
+            if event.paths.iter().any(|p| is_relevant(p)) {
+                self.pending_since = Some(Instant::now());
+            }
+        }
+
+        match self.pending_since {
+            Some(since) if since.elapsed() > DEBOUNCE => {
+                self.pending_since = None;
+                let state = FolderState::read(&self.game_path, &get_load_order(&self.game_path));
+                if self.scanned.as_ref() == Some(&state) {
+                    return false;
+                }
+                self.scanned = Some(state);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// There are changes that will be reported soon
+    pub fn is_pending(&self) -> bool {
+        self.pending_since.is_some()
+    }
+}
+
+fn is_relevant(path: &Path) -> bool {
+    let is_modlist = path
+        .file_name()
+        .is_some_and(|f| f.eq_ignore_ascii_case("modlist.txt"));
+    let is_archive = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("archive") || e.eq_ignore_ascii_case("xl"));
+    // REDmod folders, deleted ones do not exist anymore
+    let is_redmod_folder = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|f| f.eq_ignore_ascii_case("mods"));
+    let is_folder = path.is_dir() || is_redmod_folder;
+
+    is_modlist || is_archive || is_folder
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde_yaml::Value;
+
+use crate::{get_files, get_game_root, get_redmod_names};
+
+/// Kind of an ArchiveXL registration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+pub enum EXlKind {
+    /// a resource patched via resource.patch
+    Patch,
+    /// a factory csv
+    Factory,
+    /// a localization file of a language
+    Localization,
+    /// an attachment slot
+    AttachmentSlot,
+}
+
+impl fmt::Display for EXlKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EXlKind::Patch => write!(f, "patch"),
+            EXlKind::Factory => write!(f, "factory"),
+            EXlKind::Localization => write!(f, "localization"),
+            EXlKind::AttachmentSlot => write!(f, "attachment slot"),
+        }
+    }
+}
+
+/// A single registration of an .xl file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+pub struct XlEntry {
+    pub kind: EXlKind,
+    /// normalized depot path or name of the registered entry
+    pub key: String,
+}
+
+/// A parsed ArchiveXL file
+#[derive(Debug, Clone)]
+pub struct XlFile {
+    /// archive the .xl file belongs to, e.g. `my_mod.archive` for `my_mod.archive.xl`.
+    /// ArchiveXL loads every .xl file, others are named after the .xl file, e.g. `my_mod.xl`.
+    /// REDmod names are relative to the game root like in the load order.
+    pub archive_name: String,
+    pub entries: Vec<XlEntry>,
+}
+
+/// Read all .xl files of an archive folder and of the REDmod archive folders
+pub fn read_xl_files(game_path: &Path) -> Vec<XlFile> {
+    get_xl_paths(game_path)
+        .into_iter()
+        .filter_map(|(prefix, path)| read_xl_file(&path, &prefix))
+        .collect()
+}
+
+/// Paths of the .xl files of an archive folder and of the REDmod archive folders,
+/// with the prefix of the archive names in their folder
+pub fn get_xl_paths(game_path: &Path) -> Vec<(String, PathBuf)> {
+    let mut folders = vec![(String::new(), game_path.to_path_buf())];
+    if let Some(root) = get_game_root(game_path) {
+        for mod_name in get_redmod_names(&root) {
+            folders.push((
+                format!("mods/{}/archives/", mod_name),
+                root.join("mods").join(&mod_name).join("archives"),
+            ));
+        }
+    }
+
+    let mut paths = vec![];
+    for (prefix, folder) in folders {
+        for path in get_files(&folder, "xl") {
+            paths.push((prefix.clone(), path));
+        }
+    }
+    paths
+}
+
+fn read_xl_file(path: &Path, prefix: &str) -> Option<XlFile> {
+    let file_name = path.file_name()?.to_string_lossy().into_owned();
+    let archive_name = match path.file_stem().map(|s| s.to_string_lossy().into_owned()) {
+        Some(stem) if stem.ends_with(".archive") && path.with_file_name(&stem).exists() => stem,
+        _ => file_name,
+    };
+    let archive_name = format!("{}{}", prefix, archive_name);
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            log::error!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    match serde_yaml::from_str::<Value>(&text) {
+        Ok(yaml) => Some(XlFile {
+            archive_name,
+            entries: parse_xl(&yaml),
+        }),
+        Err(e) => {
+            log::error!("Failed to parse {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Get all registrations of an .xl file
+pub fn parse_xl(yaml: &Value) -> Vec<XlEntry> {
+    let mut entries = vec![];
+
+    // resource:
+    //   patch:
+    //     patch\file.mesh:
+    //       - target\file.mesh
+    if let Some(patches) = yaml
+        .get("resource")
+        .and_then(|r| r.get("patch"))
+        .and_then(|p| p.as_mapping())
+    {
+        for targets in patches.values() {
+            for target in get_strings(targets) {
+                entries.push(XlEntry {
+                    kind: EXlKind::Patch,
+                    key: normalize_path(&target),
+                });
+            }
+        }
+    }
+
+    // factories:
+    //   - path\factory.csv
+    if let Some(factories) = yaml.get("factories") {
+        for factory in get_strings(factories) {
+            entries.push(XlEntry {
+                kind: EXlKind::Factory,
+                key: normalize_path(&factory),
+            });
+        }
+    }
+
+    // localization:
+    //   onscreens:
+    //     en-us: path\en-us.json
+    //   subtitles:
+    //     en-us: path\subtitles.json
+    if let Some(localization) = yaml.get("localization") {
+        for category in ["onscreens", "subtitles"] {
+            let Some(languages) = localization.get(category).and_then(|l| l.as_mapping()) else {
+                continue;
+            };
+            for (language, paths) in languages {
+                for path in get_strings(paths) {
+                    entries.push(XlEntry {
+                        kind: EXlKind::Localization,
+                        key: format!(
+                            "{}: {}",
+                            language.as_str().unwrap_or_default(),
+                            normalize_path(&path)
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    // attachmentSlots:
+    //   OutfitSlots.Head:
+    //     parentSlot: AttachmentSlots.Head
+    if let Some(slots) = yaml.get("attachmentSlots").and_then(|s| s.as_mapping()) {
+        for slot in slots.keys().filter_map(|k| k.as_str()) {
+            entries.push(XlEntry {
+                kind: EXlKind::AttachmentSlot,
+                key: slot.to_owned(),
+            });
+        }
+    }
+
+    entries.sort();
+    entries.dedup();
+    entries
+}
+
+/// Get registrations that are made by more than one archive, mapped to the archive names
+pub fn get_xl_conflicts(xl_files: &[XlFile]) -> HashMap<XlEntry, Vec<String>> {
+    let mut map: HashMap<XlEntry, Vec<String>> = HashMap::default();
+    for xl_file in xl_files {
+        for entry in &xl_file.entries {
+            let archives = map.entry(entry.clone()).or_default();
+            if !archives.contains(&xl_file.archive_name) {
+                archives.push(xl_file.archive_name.to_owned());
+            }
+        }
+    }
+    map.retain(|_, archives| archives.len() > 1);
+    map
+}
+
+/// A yaml value that is either a string or a list of strings
+fn get_strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.to_owned()],
+        Value::Sequence(seq) => seq
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(|s| s.to_owned())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Depot paths are case-insensitive and may use either slash
+fn normalize_path(path: &str) -> String {
+    path.trim().replace('/', "\\").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: EXlKind, key: &str) -> XlEntry {
+        XlEntry {
+            kind,
+            key: key.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parse_and_conflicts() {
+        let yaml = |text: &str| parse_xl(&serde_yaml::from_str(text).unwrap());
+        let a = yaml(
+            r#"
+resource:
+  patch:
+    patch\a.mesh: [base/Target.mesh, base\other.mesh]
+factories:
+  - a\factory.csv
+localization:
+  onscreens:
+    en-us: a\en-us.json
+  subtitles:
+    en-us: [a\subtitles.json]
+attachmentSlots:
+  OutfitSlots.Head:
+    parentSlot: AttachmentSlots.Head
+"#,
+        );
+        assert_eq!(
+            a,
+            [
+                entry(EXlKind::Patch, "base\\other.mesh"),
+                entry(EXlKind::Patch, "base\\target.mesh"),
+                entry(EXlKind::Factory, "a\\factory.csv"),
+                entry(EXlKind::Localization, "en-us: a\\en-us.json"),
+                entry(EXlKind::Localization, "en-us: a\\subtitles.json"),
+                entry(EXlKind::AttachmentSlot, "OutfitSlots.Head"),
+            ]
+        );
+
+        let b = yaml(
+            r#"
+resource:
+  patch:
+    patch\b.mesh: base\target.mesh
+factories: A/Factory.csv
+localization:
+  onscreens:
+    en-us: a\en-us.json
+    de-de: a\en-us.json
+"#,
+        );
+        let conflicts = get_xl_conflicts(&[
+            XlFile {
+                archive_name: "a.archive".to_owned(),
+                entries: a,
+            },
+            XlFile {
+                archive_name: "b.xl".to_owned(),
+                entries: b,
+            },
+        ]);
+        let mut keys = conflicts.keys().cloned().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                entry(EXlKind::Patch, "base\\target.mesh"),
+                entry(EXlKind::Factory, "a\\factory.csv"),
+                entry(EXlKind::Localization, "en-us: a\\en-us.json"),
+            ]
+        );
+        assert!(conflicts.values().all(|a| a == &["a.archive", "b.xl"]));
+    }
+}